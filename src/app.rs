@@ -1,13 +1,32 @@
+mod activity;
 mod config;
+mod counter;
+mod event;
+pub(crate) mod export;
+mod history;
+mod hook;
 mod info;
+mod journal;
+mod mqtt;
+mod notify;
+mod overlay;
+mod sound;
+mod state;
+mod stats;
+mod task;
+mod template;
 mod timer;
 mod update;
 mod view;
+mod webhook;
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::NaiveDateTime;
 
 use config::ChangeConfig;
 use configu::Config;
-use iced::{event, time, window, Element, Event, Point, Subscription, Task, Theme};
-use notify_rust::Notification;
+use iced::{time, window, Element, Event, Point, Subscription, Task, Theme};
 use tray_icon::{
   menu::{Menu, MenuId, MenuItem},
   TrayIcon, TrayIconBuilder, TrayIconEvent,
@@ -15,37 +34,80 @@ use tray_icon::{
 
 use crate::{app::config::ConfigEvent, subscription, APP_NAME};
 
+const OUTBOX_FILE: &str = "timer.outbox";
+
 pub struct App {
   // ui
   pub current_theme: Theme,
   pub page: Page,
+  pub stats: stats::StatsPage,
+  pub task_form: task::Form,
   pub info: Option<String>,
   pub info_handle: Option<iced::task::Handle>,
+  // notices delivered through the banner backend
+  pub banner: Option<String>,
+  // phase notification repeated until acknowledged
+  pub escalation: Option<Escalating>,
   pub window_pos: Option<Point>,
   #[cfg(debug_assertions)]
   pub debug_mode: bool,
 
   // app
   pub window: Option<window::Id>,
+  // break overlay
+  pub overlay: Option<window::Id>,
+  pub overlay_dismissed: bool,
+  // the break goes on, but the overlay stays hidden until then
+  pub overlay_postponed: Option<NaiveDateTime>,
   pub task_tray: TrayIcon,
-  pub notification: Notification,
+  pub notifiers: notify::Notifiers,
+  pub audio: Arc<dyn sound::output::AudioOutput>,
+  pub ambient: Option<sound::ambient::Ambient>,
+  pub hooks: hook::Hooks,
+  pub outbox: Arc<webhook::Outbox>,
+  pub mqtt: Option<rumqttc::AsyncClient>,
 
   // config
   pub config: Config<config::UserConfig>,
 
   // timer
   pub timer: timer::Timer,
+  pub counters: Vec<counter::Counter>,
+  pub tasks: Config<task::TaskList>,
+  pub suggestions: activity::Suggestions,
+  // suggested for the current break
+  pub activity: Option<activity::Activity>,
+  pub state: Config<state::AppState>,
+  // journal and state file writes
+  pub writer: state::Writer,
+  // reads, the writes go through the recorder
+  pub history: Option<history::History>,
+  pub recorder: Option<history::Recorder>,
+  // phase being tracked for the history
+  pub session: Option<history::Current>,
+  // saved timer waiting for the user to resume or discard it
+  pub resume: Option<timer::TimerState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Escalating {
+  pub event: timer::TimerEvent,
+  pub sent: u32,
 }
 
 #[derive(Debug, Clone)]
 pub enum Page {
   Main,
   Config,
+  Stats,
+  Tasks,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
   Tick,
+  // redraw only
+  Refresh,
 
   // window
   WindowEvent((window::Event, window::Id)),
@@ -61,11 +123,38 @@ pub enum Message {
 
   // ui
   ChangePage(Page),
+  Stats(stats::StatsMessage),
+  Export(export::Format),
+  Task(task::TaskMessage),
   Info(info::Info),
 
   // true = stop, false = start
   Pause(bool),
-  Notify,
+  Snooze,
+  Skip,
+  Postpone,
+  ActivityDone,
+  // +1 on the named counter
+  Count(String),
+  // true = resume saved timer, false = discard it
+  Resume(bool),
+  Notify(timer::TimerEvent),
+  Notified(notify::Report),
+  HookFinished(Result<(), String>),
+  WebhookFinished(Result<(), String>),
+  // retry the webhook outbox
+  FlushWebhooks,
+  MqttEvent(mqtt::MqttEvent),
+  MqttPublished(Result<(), String>),
+  // time trackers written
+  Synced(Result<(), String>),
+  Played(Result<(), String>),
+  // a finished phase is in the history
+  Recorded(Result<history::Session, String>),
+  Escalate,
+  Acknowledge,
+  NotificationAction(notify::action::Action),
+  DismissBanner,
 
   #[cfg(debug_assertions)]
   ChangeDebugMode(bool),
@@ -73,10 +162,16 @@ pub enum Message {
 
 impl App {
   pub const SHOW_ID: &str = "show";
+  pub const SNOOZE_ID: &str = "snooze";
+  pub const ACKNOWLEDGE_ID: &str = "acknowledge";
   pub const QUIT_ID: &str = "quit";
+  // followed by the counter name
+  pub const COUNT_ID: &str = "count:";
 
   pub(crate) fn update(&mut self, message: Message) -> Task<Message> {
-    update::update(self, message)
+    let task = update::update(self, message);
+    sound::sync_ambient(self);
+    Task::batch([task, overlay::sync(self)])
   }
 
   pub(crate) fn view(&self, id: window::Id) -> Element<Message> {
@@ -88,17 +183,65 @@ impl App {
   }
 
   pub(crate) fn subscription(&self) -> Subscription<Message> {
-    Subscription::batch([
-      event::listen_with(|e, _status, id| match e {
+    let mut subscriptions = vec![
+      iced::event::listen_with(|e, _status, id| match e {
         Event::Window(e) => Some(Message::WindowEvent((e, id))),
         _ => None,
       }),
-      time::every(self.config.check_rate.clone().into()).map(|_| Message::Tick),
       subscription::tray_listener().map(|e| match e {
         subscription::TrayEvent::MenuEvent(id) => Message::TrayMenuEvent(id),
         subscription::TrayEvent::IconEvent(e) => Message::TrayIconEvent(e),
       }),
-    ])
+    ];
+
+    subscriptions.push(
+      Subscription::run_with_id("notification-actions", subscription::action_listener(notify::action::receiver()))
+        .map(Message::NotificationAction),
+    );
+
+    for source in self.config.task_sources.iter().filter(|v| v.watch) {
+      subscriptions.push(
+        subscription::file_changes(source.path.clone()).map(|v| Message::Task(task::TaskMessage::Changed(v))),
+      );
+    }
+
+    if let Some(config) = &self.config.mqtt {
+      subscriptions.push(mqtt::listen(config.clone()).map(Message::MqttEvent));
+    }
+
+    for deadline in [self.timer.deadline(), self.timer.warning_deadline()].into_iter().flatten() {
+      subscriptions.push(subscription::deadline(deadline).map(|_| Message::Tick));
+    }
+
+    if let Some(deadline) = self.overlay_postponed {
+      subscriptions.push(subscription::deadline(deadline).map(|_| Message::Tick));
+    }
+
+    for deadline in counter::deadlines(self) {
+      subscriptions.push(subscription::deadline(deadline).map(|_| Message::Tick));
+    }
+
+    if !self.config.webhooks.is_empty() {
+      subscriptions.push(time::every(webhook::FLUSH_INTERVAL).map(|_| Message::FlushWebhooks));
+    }
+
+    if let (Some(_), Some(escalation)) = (&self.escalation, &self.config.notification.escalation) {
+      let interval: Duration = (&escalation.interval).into();
+      // `time::every` panics on a zero interval, `config::check` reports it
+      if !interval.is_zero() {
+        subscriptions.push(time::every(interval).map(|_| Message::Escalate));
+      }
+    }
+
+    if self.window.is_some() {
+      subscriptions.push(time::every(Duration::from_secs(1)).map(|_| Message::Refresh));
+    }
+
+    if let Some(check_rate) = &self.config.check_rate {
+      subscriptions.push(time::every(check_rate.into()).map(|_| Message::Tick));
+    }
+
+    Subscription::batch(subscriptions)
   }
 
   pub(crate) fn run() -> (App, Task<Message>) {
@@ -108,18 +251,10 @@ impl App {
     // config
 
     // task tray
-    let menu = Menu::new();
-    menu
-      .append_items(&[
-        &MenuItem::with_id(Self::SHOW_ID, "show", true, None),
-        &MenuItem::with_id(Self::QUIT_ID, "quit", true, None),
-      ])
-      .expect("failed to append tray items");
-
     let task_tray = TrayIconBuilder::new()
       .with_icon(crate::util::icon::load_tray_icon())
       .with_menu_on_left_click(false)
-      .with_menu(Box::new(menu))
+      .with_menu(Box::new(tray_menu(&config.counters)))
       .with_title(APP_NAME)
       .with_tooltip(APP_NAME)
       .build()
@@ -127,30 +262,107 @@ impl App {
     // task tray
 
     // state
-    let notification = config.notification.clone().into();
+    let notifiers = notify::Notifiers::new(&config.notification);
+    let audio = sound::output(&config.sound);
     let duration = (&config.duration).into();
 
     let mut timer = timer::Timer::default();
     timer.duration = duration;
+    timer.warning = config.warning.as_ref().map(Duration::from);
+
+    let counters = counter::counters(&config.counters);
+
+    let (suggestions, activity_error) = match activity::Suggestions::load() {
+      Ok(v) => (v, None),
+      Err(e) => (activity::Suggestions::new(Vec::new()), Some(format!("failed to load activities: {e}"))),
+    };
+
+    let (task_list, tasks_error) = task::open();
+
+    let state = state::open();
+    let (journal, replay) = journal::open().unzip();
+    let replay = replay.unwrap_or_default();
+    // the journal is only left behind when the app did not shut down cleanly
+    let resume = replay.state.clone().or_else(|| state.timer.clone());
 
     let mut app_state = App {
       window: None,
+      overlay: None,
+      overlay_dismissed: false,
+      overlay_postponed: None,
       info: None,
       info_handle: None,
+      banner: None,
+      escalation: None,
       window_pos: Some(Point::default()),
       #[cfg(debug_assertions)]
       debug_mode: true,
       current_theme: Theme::Dark,
       page: Page::Main,
+      stats: stats::StatsPage::default(),
+      task_form: task::Form::default(),
       task_tray,
-      notification,
+      notifiers,
+      audio,
+      ambient: None,
+      hooks: hook::Hooks::new(config.hook_limit),
+      outbox: webhook::Outbox::new(config::path(OUTBOX_FILE)),
+      mqtt: None,
       config,
       timer,
+      counters,
+      tasks: task_list,
+      suggestions,
+      activity: None,
+      state,
+      writer: state::writer(journal),
+      history: history::open(),
+      recorder: history::recorder(),
+      session: None,
+      resume,
     };
 
     config::load(&mut app_state);
     // state
 
-    (app_state, Task::done(Message::WindowCreateRequested))
+    let mut tasks = vec![Task::done(Message::WindowCreateRequested), Task::done(Message::Tick)];
+    tasks.extend(config::check(&app_state.config).into_iter().map(info::send));
+    tasks.extend(activity_error.map(info::send));
+    tasks.extend(tasks_error.map(info::send));
+    tasks.push(webhook::flush(&app_state));
+    tasks.push(task::import_all(&mut app_state));
+    if replay.dropped > 0 {
+      tasks.push(info::send(format!(
+        "recovered {} journal entries, dropped a corrupt tail",
+        replay.entries
+      )));
+    }
+
+    (app_state, Task::batch(tasks))
   }
 }
+
+/// Tray menu with a +1 entry for every counter.
+fn tray_menu(counters: &[config::CounterConfig]) -> Menu {
+  let menu = Menu::new();
+  menu
+    .append_items(&[
+      &MenuItem::with_id(App::SHOW_ID, "show", true, None),
+      &MenuItem::with_id(App::SNOOZE_ID, "snooze", true, None),
+      &MenuItem::with_id(App::ACKNOWLEDGE_ID, "acknowledge", true, None),
+    ])
+    .expect("failed to append tray items");
+
+  for counter in counters {
+    let id = format!("{}{}", App::COUNT_ID, counter.name);
+    menu
+      .append(&MenuItem::with_id(id, format!("+1 {}", counter.name), true, None))
+      .expect("failed to append tray items");
+  }
+
+  menu
+    .append(&MenuItem::with_id(App::QUIT_ID, "quit", true, None))
+    .expect("failed to append tray items");
+
+  menu
+}
@@ -0,0 +1,153 @@
+use std::{
+  collections::VecDeque,
+  fs::OpenOptions,
+  io::Write,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use super::config;
+
+const ACTIVITY_FILE: &str = "activities.toml";
+const LOG_FILE: &str = "activities.log";
+
+const BUILTIN: &[(&str, &str)] = &[
+  ("stretch", "Stand up and stretch your arms over your head"),
+  ("stretch", "Roll your shoulders and neck slowly"),
+  ("stretch", "Stretch your wrists and fingers"),
+  ("hydration", "Drink a glass of water"),
+  ("hydration", "Refill your water bottle"),
+  ("walk", "Take a short walk around the room"),
+  ("walk", "Walk to a window and back"),
+  ("eyes", "Look at something far away for 20 seconds"),
+  ("eyes", "Close your eyes and breathe deeply for a minute"),
+];
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+  pub title: String,
+  #[serde(default)]
+  pub category: Option<String>,
+}
+
+/// Layout of `activities.toml`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ActivityFile {
+  // leave out the built-in list
+  pub replace_builtin: bool,
+  pub activity: Vec<Activity>,
+}
+
+/// Picks break activities at random without repeating recent ones.
+#[derive(Debug, Clone)]
+pub struct Suggestions {
+  activities: Vec<Activity>,
+  recent: VecDeque<usize>,
+  rng: u64,
+}
+
+impl Suggestions {
+  pub fn new(activities: Vec<Activity>) -> Self {
+    let seed = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|v| v.as_nanos() as u64)
+      .unwrap_or_default();
+
+    Self {
+      activities,
+      recent: VecDeque::new(),
+      rng: seed | 1,
+    }
+  }
+
+  /// The built-in list plus `activities.toml` next to the executable.
+  pub fn load() -> Result<Self, String> {
+    let file = config::open::<ActivityFile>(config::path(ACTIVITY_FILE)).map_err(|e| e.to_string())?;
+
+    let builtin = BUILTIN.iter().map(|(category, title)| Activity {
+      title: title.to_string(),
+      category: Some(category.to_string()),
+    });
+
+    let activities = match file.replace_builtin {
+      true => file.activity.clone(),
+      false => builtin.chain(file.activity.iter().cloned()).collect(),
+    };
+
+    Ok(Self::new(activities))
+  }
+
+  pub fn next(&mut self) -> Option<Activity> {
+    if self.activities.is_empty() {
+      return None;
+    }
+
+    // anything picked in the last half of the list is left out
+    let window = self.activities.len() / 2;
+    while self.recent.len() > window {
+      self.recent.pop_front();
+    }
+
+    let candidates: Vec<usize> = (0..self.activities.len())
+      .filter(|i| !self.recent.contains(i))
+      .collect();
+    let picked = candidates[self.random() as usize % candidates.len()];
+
+    self.recent.push_back(picked);
+    Some(self.activities[picked].clone())
+  }
+
+  // xorshift
+  fn random(&mut self) -> u64 {
+    self.rng ^= self.rng << 13;
+    self.rng ^= self.rng >> 7;
+    self.rng ^= self.rng << 17;
+    self.rng
+  }
+}
+
+/// Appends a completed activity to `activities.log`.
+pub fn log(activity: &Activity) -> Result<(), String> {
+  let path = config::path(LOG_FILE);
+
+  let mut file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)
+    .map_err(|e| format!("{}: {e}", path.display()))?;
+
+  writeln!(
+    file,
+    "{}\t{}\t{}",
+    Local::now().format("%Y-%m-%d %H:%M:%S"),
+    activity.category.as_deref().unwrap_or_default(),
+    activity.title
+  )
+  .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn no_repeat_too_soon() {
+    let activities: Vec<_> = (0..6)
+      .map(|i| Activity {
+        title: i.to_string(),
+        category: None,
+      })
+      .collect();
+    let mut suggestions = Suggestions::new(activities);
+
+    let picked: Vec<_> = (0..100).map(|_| suggestions.next().unwrap().title).collect();
+    for window in picked.windows(4) {
+      for (i, a) in window.iter().enumerate() {
+        assert!(!window[i + 1..].contains(a), "{a} repeated in {window:?}");
+      }
+    }
+  }
+}
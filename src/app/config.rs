@@ -1,13 +1,23 @@
-use std::{env::current_exe, time::Duration};
+use std::{collections::HashMap, env::current_exe, path::PathBuf, time::Duration};
 
 use configu::{Config, Configurable};
 use iced::Theme;
 use notify_rust::{Notification, Timeout};
 use serde::{Deserialize, Serialize};
 
-use crate::{APPID, APP_NAME};
+#[cfg(target_os = "windows")]
+use crate::APPID;
+use crate::APP_NAME;
 
-use super::timer::ticker::Ticker;
+use super::{
+  counter,
+  event::{Event, EventKind},
+  hook::Hooks,
+  notify::Notifiers,
+  sound::{self, ambient::Noise},
+  template,
+  timer::{ticker::Ticker, TimerEvent},
+};
 
 const CONFIG_FILE: &str = "timer.toml";
 
@@ -30,49 +40,115 @@ pub enum ChangeConfig {
 pub struct UserConfig {
   #[serde(with = "theme")]
   pub theme: Theme,
-  pub check_rate: Hms,
+  // fallback polling on top of the deadline wakeups
+  pub check_rate: Option<Hms>,
   pub duration: Hms,
-  pub notification: NotificationLike,
+  // notify this long before a phase ends
+  pub warning: Option<Hms>,
+  pub snooze: Hms,
+  #[serde(with = "ticker")]
+  pub ticker: Ticker,
+  pub notification: NotificationConfig,
+  pub sound: SoundConfig,
+  // keyed by ticker name
+  pub ambient: HashMap<String, AmbientConfig>,
+  pub hooks: Vec<HookConfig>,
+  // hooks allowed to run at the same time
+  pub hook_limit: usize,
+  pub webhooks: Vec<WebhookConfig>,
+  pub mqtt: Option<MqttConfig>,
+  // fullscreen window during breaks
+  pub overlay: Option<OverlayConfig>,
+  // habit counters such as glasses of water
+  pub counters: Vec<CounterConfig>,
+  // completed work phases are also written to these
+  pub timewarrior: Option<TimewarriorConfig>,
+  pub org: Option<OrgConfig>,
+  // todo.txt and Markdown checklists to take tasks from
+  pub task_sources: Vec<TaskSourceConfig>,
 }
 
 impl Default for UserConfig {
   fn default() -> Self {
     Self {
       theme: Theme::Dark,
-      check_rate: Hms::ZERO.second(3),
+      check_rate: None,
       duration: Hms::default(),
-      notification: NotificationLike::default(),
+      warning: None,
+      snooze: Hms::ZERO.minute(5),
+      ticker: Ticker::default(),
+      notification: NotificationConfig::default(),
+      sound: SoundConfig::default(),
+      ambient: HashMap::new(),
+      hooks: Vec::new(),
+      hook_limit: 4,
+      webhooks: Vec::new(),
+      mqtt: None,
+      overlay: None,
+      counters: Vec::new(),
+      timewarrior: None,
+      org: None,
+      task_sources: Vec::new(),
     }
   }
 }
 
 pub(crate) fn config<T: for<'de> Deserialize<'de> + Serialize + Default>() -> Result<Config<T>, configu::Error> {
-  let config_file = current_exe()
-    .expect("failed to get current exe")
-    .parent()
-    .expect("failed to get parent directory")
-    .join(CONFIG_FILE);
+  let config_file = path(CONFIG_FILE);
 
   let is_file = config_file.is_file();
-  let mut config = Config::<T>::open(Some(config_file));
+  let mut config = open::<T>(config_file)?;
+
+  if !is_file {
+    config.file_path = None;
+  }
+
+  Ok(config)
+}
+
+/// Opens `file`, falling back to `T::default()` if it does not exist yet.
+pub(crate) fn open<T: for<'de> Deserialize<'de> + Serialize + Default>(
+  file: PathBuf,
+) -> Result<Config<T>, configu::Error> {
+  let is_file = file.is_file();
+  let mut config = Config::<T>::open(Some(file));
 
   if is_file {
     config.load()?;
   } else {
-    config.file_path = None;
     *config = T::default();
   }
 
   Ok(config)
 }
 
+/// Returns the path of `file_name` next to the executable.
+pub(crate) fn path(file_name: &str) -> PathBuf {
+  current_exe()
+    .expect("failed to get current exe")
+    .parent()
+    .expect("failed to get parent directory")
+    .join(file_name)
+}
+
 pub(crate) fn load(app: &mut super::App) {
   dbg!(&app.config.file_path);
   app.config.load().or_else(uncheck_path_not_specified).unwrap();
 
   app.current_theme = app.config.theme.clone();
-  app.notification = app.config.notification.clone().into();
+  app.notifiers = Notifiers::new(&app.config.notification);
+  app.hooks = Hooks::new(app.config.hook_limit);
+  app.counters = counter::counters(&app.config.counters);
+  app.task_tray.set_menu(Some(Box::new(super::tray_menu(&app.config.counters))));
+  if let Some(ambient) = app.ambient.take() {
+    ambient.fade_out();
+  }
+  app.audio = sound::output(&app.config.sound);
   app.timer.duration = (&app.config.duration).into();
+  app.timer.warning = app.config.warning.as_ref().map(Duration::from);
+  if app.timer.ticker.name != app.config.ticker.name {
+    app.timer.ticker = app.config.ticker.clone();
+  }
 
   println!("config loaded");
 }
@@ -80,12 +156,46 @@ pub(crate) fn load(app: &mut super::App) {
 pub(crate) fn save(app: &mut super::App) {
   app.config.theme = app.current_theme.clone();
   app.config.duration = app.timer.duration.into();
+  app.config.ticker = app.timer.ticker.clone();
 
   app.config.save().or_else(uncheck_path_not_specified).unwrap();
 
   println!("config saved");
 }
 
+/// Returns a description of every unknown template placeholder.
+pub(crate) fn check(config: &UserConfig) -> Vec<String> {
+  let notification = &config.notification;
+
+  [("notification", &notification.desktop.summary), ("notification", &notification.desktop.body)]
+    .into_iter()
+    .chain(
+      notification
+        .templates
+        .iter()
+        .flat_map(|(event, template)| [(event, &template.summary), (event, &template.body)]),
+    )
+    .flat_map(|(name, template)| {
+      template::unknown(template)
+        .into_iter()
+        .map(move |v| format!("unknown placeholder {{{v}}} in {name}"))
+    })
+    .chain(config.webhooks.iter().flat_map(|webhook| {
+      let body = webhook.body.as_deref().unwrap_or_default();
+      template::unknown_in(body, Event::PLACEHOLDERS)
+        .into_iter()
+        .map(move |v| format!("unknown placeholder {{{v}}} in webhook {}", webhook.url))
+    }))
+    .chain(
+      notification
+        .escalation
+        .as_ref()
+        .filter(|v| v.interval.as_seconds() == 0)
+        .map(|_| "escalation interval must be longer than 0s, notifications are not repeated".to_owned()),
+    )
+    .collect()
+}
+
 fn uncheck_path_not_specified(err: configu::Error) -> Result<(), configu::Error> {
   match err {
     configu::Error::PathNotSpecified => Ok(()),
@@ -93,6 +203,27 @@ fn uncheck_path_not_specified(err: configu::Error) -> Result<(), configu::Error>
   }
 }
 
+pub(crate) mod ticker {
+  use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+  use crate::app::timer::ticker::Ticker;
+
+  pub fn serialize<S>(t: &Ticker, s: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    s.serialize_str(t.name)
+  }
+
+  pub fn deserialize<'de, D>(d: D) -> Result<Ticker, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let s = String::deserialize(d)?;
+    Ticker::from_name(&s).ok_or_else(|| D::Error::custom(format!("unknown ticker: {s}")))
+  }
+}
+
 mod theme {
   use iced::Theme;
   use serde::{Deserialize, Deserializer, Serializer};
@@ -120,7 +251,7 @@ mod theme {
   }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Hms {
   pub hour: u8,
   pub minute: u8,
@@ -259,6 +390,343 @@ impl From<&Duration> for Hms {
   }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct NotificationConfig {
+  pub backends: Vec<BackendConfig>,
+  // settings of the desktop backend, also the text of every notice
+  #[serde(flatten)]
+  pub desktop: NotificationLike,
+  // defaults to `timer.log` next to the executable
+  pub log_file: Option<PathBuf>,
+  // program and arguments, the summary and body are appended
+  pub command: Vec<String>,
+  pub templates: Templates,
+  // snooze/skip/start buttons where the desktop supports them
+  pub actions: bool,
+  pub escalation: Option<Escalation>,
+  pub speech: SpeechConfig,
+}
+
+impl Default for NotificationConfig {
+  fn default() -> Self {
+    Self {
+      backends: vec![BackendConfig {
+        backend: Backend::Desktop,
+        fallback: vec![Backend::Banner],
+      }],
+      desktop: NotificationLike::default(),
+      log_file: None,
+      command: Vec::new(),
+      templates: Templates::default(),
+      actions: true,
+      escalation: None,
+      speech: SpeechConfig::default(),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct SpeechConfig {
+  // program and arguments, the text is appended
+  pub command: Vec<String>,
+  pub mode: SpeechMode,
+}
+
+impl Default for SpeechConfig {
+  fn default() -> Self {
+    Self {
+      command: vec!["espeak-ng".to_owned()],
+      mode: SpeechMode::default(),
+    }
+  }
+}
+
+/// What happens when an announcement comes in while another one is still being spoken.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeechMode {
+  #[default]
+  Queue,
+  Interrupt,
+}
+
+/// Repeats phase notifications with rising urgency until they are acknowledged.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Escalation {
+  pub interval: Hms,
+  // repetitions after the first notification
+  pub max: u32,
+}
+
+impl Default for Escalation {
+  fn default() -> Self {
+    Self {
+      interval: Hms::ZERO.minute(1),
+      max: 3,
+    }
+  }
+}
+
+/// Per event text, falling back to `summary` and `body` of the notification.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Templates {
+  pub work_end: Option<Template>,
+  pub break_end: Option<Template>,
+  pub warning: Option<Template>,
+}
+
+impl Templates {
+  pub fn get(&self, event: TimerEvent) -> Option<&Template> {
+    match event {
+      TimerEvent::WorkEnd => self.work_end.as_ref(),
+      TimerEvent::BreakEnd => self.break_end.as_ref(),
+      TimerEvent::Warning => self.warning.as_ref(),
+    }
+  }
+
+  fn iter(&self) -> impl Iterator<Item = (&'static str, &Template)> {
+    [
+      ("work_end", &self.work_end),
+      ("break_end", &self.break_end),
+      ("warning", &self.warning),
+    ]
+    .into_iter()
+    .filter_map(|(name, v)| v.as_ref().map(|v| (name, v)))
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Template {
+  pub summary: String,
+  pub body: String,
+}
+
+/// No sounds unless an event is given one.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SoundConfig {
+  pub output: Output,
+  pub work_end: Option<Sound>,
+  pub break_end: Option<Sound>,
+  pub warning: Option<Sound>,
+}
+
+impl SoundConfig {
+  pub fn get(&self, event: TimerEvent) -> Option<&Sound> {
+    match event {
+      TimerEvent::WorkEnd => self.work_end.as_ref(),
+      TimerEvent::BreakEnd => self.break_end.as_ref(),
+      TimerEvent::Warning => self.warning.as_ref(),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Output {
+  #[default]
+  Default,
+  Null,
+  // render to a WAV file instead of playing
+  File(PathBuf),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Sound {
+  // WAV or OGG file, the bundled chime if unset
+  pub file: Option<PathBuf>,
+  pub volume: f32,
+}
+
+impl Default for Sound {
+  fn default() -> Self {
+    Self { file: None, volume: 0.5 }
+  }
+}
+
+/// Shell command run on a timer event.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct HookConfig {
+  pub event: EventKind,
+  pub command: String,
+  #[serde(default = "HookConfig::default_timeout")]
+  pub timeout: Hms,
+}
+
+impl HookConfig {
+  fn default_timeout() -> Hms {
+    Hms::ZERO.second(30)
+  }
+}
+
+/// JSON POST sent for timer events.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct WebhookConfig {
+  pub url: String,
+  // every event if empty
+  pub events: Vec<EventKind>,
+  pub headers: HashMap<String, String>,
+  // template with the event fields as placeholders, the event as JSON if unset
+  pub body: Option<String>,
+  pub retries: u32,
+  // doubled after every failed attempt
+  pub backoff: Hms,
+}
+
+impl Default for WebhookConfig {
+  fn default() -> Self {
+    Self {
+      url: String::new(),
+      events: Vec::new(),
+      headers: HashMap::new(),
+      body: None,
+      retries: 3,
+      backoff: Hms::ZERO.second(2),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct OverlayConfig {
+  pub postpone: Hms,
+  pub max_postpones: u32,
+  // no way to skip the break
+  pub strict: bool,
+}
+
+impl Default for OverlayConfig {
+  fn default() -> Self {
+    Self {
+      postpone: Hms::ZERO.minute(5),
+      max_postpones: 3,
+      strict: false,
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CounterConfig {
+  pub name: String,
+  // daily goal
+  #[serde(default)]
+  pub target: Option<u32>,
+  // remind this long after the last reminder or +1
+  #[serde(default)]
+  pub remind: Option<Hms>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TaskSourceConfig {
+  // `.md` and `.markdown` are read as checklists, anything else as todo.txt
+  pub path: PathBuf,
+  // import again whenever the file changes
+  #[serde(default)]
+  pub watch: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TimewarriorConfig {
+  // defaults to the data directory Timewarrior itself uses
+  pub data_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct OrgConfig {
+  // defaults to `timer.org` next to the executable
+  pub file: Option<PathBuf>,
+  // clocks go into a subheading per task below this one
+  pub heading: String,
+}
+
+impl Default for OrgConfig {
+  fn default() -> Self {
+    Self {
+      file: None,
+      heading: "* Focus".to_owned(),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct MqttConfig {
+  pub host: String,
+  pub port: u16,
+  pub client_id: String,
+  pub username: Option<String>,
+  pub password: Option<String>,
+  // retained phase, remaining seconds and pause state
+  pub state_topic: String,
+  pub event_topic: String,
+  // accepts `pause`, `resume`, `skip` and `snooze`
+  pub command_topic: String,
+}
+
+impl Default for MqttConfig {
+  fn default() -> Self {
+    Self {
+      host: "localhost".to_owned(),
+      port: 1883,
+      client_id: "timer".to_owned(),
+      username: None,
+      password: None,
+      state_topic: "timer/state".to_owned(),
+      event_topic: "timer/event".to_owned(),
+      command_topic: "timer/command".to_owned(),
+    }
+  }
+}
+
+/// Sound generated during work phases.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct AmbientConfig {
+  pub noise: Option<Noise>,
+  // a clock ticking every second
+  pub ticking: bool,
+  pub volume: f32,
+}
+
+impl Default for AmbientConfig {
+  fn default() -> Self {
+    Self {
+      noise: Some(Noise::Pink),
+      ticking: false,
+      volume: 0.3,
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BackendConfig {
+  pub backend: Backend,
+  // tried in order when `backend` fails
+  #[serde(default)]
+  pub fallback: Vec<Backend>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+  Desktop,
+  Stdout,
+  Stderr,
+  LogFile,
+  Banner,
+  Command,
+  Speech,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(default)]
 pub struct NotificationLike {
@@ -286,8 +754,11 @@ impl Default for NotificationLike {
       summary: "Elapsed now".to_owned(),
       subtitle: None,
       body: String::default(),
+      #[cfg(target_os = "windows")]
       sound_name: None,
+      #[cfg(target_os = "windows")]
       path_to_image: None,
+      #[cfg(target_os = "windows")]
       app_id: APPID.to_owned(),
       timeout: TimeoutLike::default(),
     }
@@ -302,14 +773,17 @@ impl From<NotificationLike> for Notification {
     notification.subtitle = value.subtitle;
     notification.body = value.body;
 
-    if let Some(v) = value.sound_name {
-      notification.sound_name(&v);
-    }
-    if let Some(v) = value.path_to_image {
-      notification.image_path(&v);
-    }
+    #[cfg(target_os = "windows")]
+    {
+      if let Some(v) = value.sound_name {
+        notification.sound_name(&v);
+      }
+      if let Some(v) = value.path_to_image {
+        notification.image_path(&v);
+      }
 
-    notification.app_id(&value.app_id);
+      notification.app_id(&value.app_id);
+    }
 
     notification.timeout(value.timeout);
 
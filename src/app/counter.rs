@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use iced::Task;
+use serde::{Deserialize, Serialize};
+
+use super::{
+  config::CounterConfig,
+  info,
+  notify::{self, Notice},
+  state,
+  timer::Timer,
+  App, Message,
+};
+
+// days of counts kept in the state file
+const HISTORY_DAYS: i64 = 90;
+
+/// A habit counter, reminders run on their own timer with the default ticker.
+pub struct Counter {
+  pub name: String,
+  pub target: Option<u32>,
+  pub reminder: Option<Timer>,
+}
+
+pub type Counts = BTreeMap<NaiveDate, BTreeMap<String, Count>>;
+
+/// Count of one day, the target is stored as it was on that day.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Count {
+  pub count: u32,
+  pub target: Option<u32>,
+}
+
+pub(crate) fn counters(config: &[CounterConfig]) -> Vec<Counter> {
+  config
+    .iter()
+    .map(|v| Counter {
+      name: v.name.clone(),
+      target: v.target,
+      reminder: v.remind.as_ref().map(|remind| {
+        let mut timer = Timer::default();
+        timer.duration = remind.into();
+        timer
+      }),
+    })
+    .collect()
+}
+
+/// Deadlines of every running reminder.
+pub(crate) fn deadlines(app: &App) -> impl Iterator<Item = NaiveDateTime> + '_ {
+  app
+    .counters
+    .iter()
+    .filter_map(|v| v.reminder.as_ref().and_then(Timer::deadline))
+}
+
+/// Advances the reminders and sends a notice for every one that is due.
+pub(crate) fn tick(app: &mut App) -> Task<Message> {
+  let today = Local::now().date_naive();
+  let mut tasks = Vec::new();
+
+  if let Some(summarized) = app.state.summarized.filter(|v| *v < today) {
+    if let Some((date, counts)) = app.state.counts.range(summarized..today).next_back() {
+      tasks.push(notify::notice(
+        app,
+        Notice {
+          summary: format!("Summary of {date}"),
+          body: summary(counts),
+          ..Default::default()
+        },
+      ));
+    }
+  }
+  if app.state.summarized != Some(today) {
+    app.state.summarized = Some(today);
+    state::save(app);
+  }
+
+  let mut due = Vec::new();
+  for counter in &mut app.counters {
+    if counter.reminder.as_mut().and_then(Timer::tick).is_some() {
+      due.push((counter.name.clone(), counter.target));
+    }
+  }
+
+  for (name, target) in due {
+    let count = today_count(app, &name);
+    tasks.push(notify::notice(
+      app,
+      Notice {
+        summary: format!("Time for {name}"),
+        body: progress(count, target),
+        ..Default::default()
+      },
+    ));
+  }
+
+  Task::batch(tasks)
+}
+
+/// Adds one to the count of `name` for today and restarts its reminder.
+pub(crate) fn increment(app: &mut App, name: &str) -> Task<Message> {
+  let Some(counter) = app.counters.iter_mut().find(|v| v.name == name) else {
+    return info::send(format!("unknown counter: {name}"));
+  };
+
+  if let Some(reminder) = &mut counter.reminder {
+    reminder.restart_phase();
+  }
+
+  let target = counter.target;
+  let today = Local::now().date_naive();
+  let count = add(&mut app.state.counts, today, name, target);
+  state::save(app);
+
+  info::send(format!("{name}: {}", progress(count.count, target)))
+}
+
+/// Today's count of every configured counter, with its target.
+pub(crate) fn today(app: &App) -> Vec<(&str, u32, Option<u32>)> {
+  app
+    .counters
+    .iter()
+    .map(|v| (v.name.as_str(), today_count(app, &v.name), v.target))
+    .collect()
+}
+
+/// One line per counter, such as `water 6/8`.
+pub(crate) fn summary(counts: &BTreeMap<String, Count>) -> String {
+  counts
+    .iter()
+    .map(|(name, v)| format!("{name} {}", progress(v.count, v.target)))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn today_count(app: &App, name: &str) -> u32 {
+  let today = Local::now().date_naive();
+  app
+    .state
+    .counts
+    .get(&today)
+    .and_then(|v| v.get(name))
+    .map(|v| v.count)
+    .unwrap_or_default()
+}
+
+fn add(counts: &mut Counts, date: NaiveDate, name: &str, target: Option<u32>) -> Count {
+  let count = counts.entry(date).or_default().entry(name.to_owned()).or_default();
+  count.count += 1;
+  count.target = target;
+  let count = *count;
+
+  let oldest = date - chrono::Duration::days(HISTORY_DAYS);
+  counts.retain(|v, _| *v > oldest);
+
+  count
+}
+
+fn progress(count: u32, target: Option<u32>) -> String {
+  match target {
+    Some(target) => format!("{count}/{target}"),
+    None => count.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn add_keeps_recent_days() {
+    let mut counts = BTreeMap::new();
+    let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+    add(&mut counts, day, "water", Some(8));
+    let count = add(&mut counts, day, "water", Some(8));
+    assert_eq!(count, Count { count: 2, target: Some(8) });
+
+    let later = day + chrono::Duration::days(HISTORY_DAYS);
+    add(&mut counts, later, "water", None);
+    assert_eq!(counts.keys().copied().collect::<Vec<_>>(), vec![later]);
+    assert_eq!(summary(&counts[&later]), "water 1");
+  }
+}
@@ -0,0 +1,120 @@
+use chrono::{Local, NaiveDateTime};
+use iced::Task;
+use serde::{Deserialize, Serialize};
+
+use super::{
+  hook, mqtt,
+  timer::{ticker::Phase, Timer, TimerEvent},
+  webhook, App, Message,
+};
+
+/// Everything the timer reports to the outside world.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+  WorkEnd,
+  BreakEnd,
+  Warning,
+  Pause,
+  Resume,
+  Snooze,
+  Skip,
+  // the break overlay was hidden for a while, the break itself goes on
+  Postpone,
+}
+
+impl From<TimerEvent> for EventKind {
+  fn from(value: TimerEvent) -> Self {
+    match value {
+      TimerEvent::WorkEnd => EventKind::WorkEnd,
+      TimerEvent::BreakEnd => EventKind::BreakEnd,
+      TimerEvent::Warning => EventKind::Warning,
+    }
+  }
+}
+
+impl EventKind {
+  pub fn name(&self) -> &'static str {
+    match self {
+      EventKind::WorkEnd => "work_end",
+      EventKind::BreakEnd => "break_end",
+      EventKind::Warning => "warning",
+      EventKind::Pause => "pause",
+      EventKind::Resume => "resume",
+      EventKind::Snooze => "snooze",
+      EventKind::Skip => "skip",
+      EventKind::Postpone => "postpone",
+    }
+  }
+}
+
+/// An event together with the timer state right after it.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Event {
+  pub event: EventKind,
+  pub at: NaiveDateTime,
+  pub ticker: String,
+  pub phase: Phase,
+  pub next_phase: Phase,
+  pub next: Option<NaiveDateTime>,
+  pub remaining: Option<u64>,
+  pub cycle: u32,
+  pub paused: bool,
+}
+
+impl Event {
+  /// Names usable as `{placeholder}` in webhook bodies.
+  pub const PLACEHOLDERS: &[&str] = &[
+    "event",
+    "at",
+    "ticker",
+    "phase",
+    "next_phase",
+    "next",
+    "remaining",
+    "cycle",
+    "paused",
+    "json",
+  ];
+
+  pub fn get(&self, name: &str) -> Option<String> {
+    Some(match name {
+      "event" => self.event.name().to_owned(),
+      "at" => self.at.to_string(),
+      "ticker" => self.ticker.clone(),
+      "phase" => self.phase.to_string(),
+      "next_phase" => self.next_phase.to_string(),
+      "next" => self.next.map(|v| v.to_string()).unwrap_or_default(),
+      "remaining" => self.remaining.map(|v| v.to_string()).unwrap_or_default(),
+      "cycle" => self.cycle.to_string(),
+      "paused" => self.paused.to_string(),
+      "json" => serde_json::to_string(self).ok()?,
+      _ => return None,
+    })
+  }
+
+  pub fn new(event: EventKind, timer: &Timer) -> Self {
+    Self {
+      event,
+      at: Local::now().naive_local(),
+      ticker: timer.ticker.name.to_owned(),
+      phase: timer.phase(),
+      next_phase: timer.next_phase(),
+      next: timer.deadline(),
+      remaining: timer.remaining().map(|v| v.as_secs()),
+      cycle: timer.cycle,
+      paused: !timer.enable,
+    }
+  }
+}
+
+/// Hands `kind` to everything listening for timer events.
+pub(crate) fn emit(app: &App, kind: EventKind) -> Task<Message> {
+  let event = Event::new(kind, &app.timer);
+
+  Task::batch([
+    hook::run(app, &event),
+    webhook::send(app, &event),
+    mqtt::publish(app, Some(&event)),
+  ])
+}
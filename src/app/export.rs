@@ -0,0 +1,409 @@
+mod org;
+mod timewarrior;
+
+use std::{
+  fs::{self, File},
+  io::{self, BufWriter, Write},
+  path::{Path, PathBuf},
+};
+
+use chrono::{Days, Local, NaiveDate, Utc};
+use iced::Task;
+use serde::Serialize;
+
+use super::{
+  config::{self, UserConfig},
+  history::{self, Outcome, Query, Session},
+  info::{self, Info},
+  timer::ticker::Phase,
+  App, Message,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+  Csv,
+  Json,
+  Ics,
+}
+
+impl Format {
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Format::Csv => "csv",
+      Format::Json => "json",
+      Format::Ics => "ics",
+    }
+  }
+}
+
+const ORG_FILE: &str = "timer.org";
+
+/// Time trackers that completed work phases are copied into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Target {
+  Timewarrior,
+  Org,
+}
+
+/// A session as it is written to CSV and JSON, times are local with their offset.
+#[derive(Serialize)]
+struct Record<'a> {
+  start: String,
+  end: String,
+  ticker: &'a str,
+  phase: Phase,
+  planned: u64,
+  actual: u64,
+  outcome: &'static str,
+  task: Option<&'a str>,
+  tags: &'a [String],
+}
+
+impl<'a> From<&'a Session> for Record<'a> {
+  fn from(v: &'a Session) -> Self {
+    Self {
+      start: v.start.to_rfc3339(),
+      end: v.end.to_rfc3339(),
+      ticker: &v.ticker,
+      phase: v.phase,
+      planned: v.planned.as_secs(),
+      actual: v.actual.as_secs(),
+      outcome: v.outcome.name(),
+      task: v.task.as_deref(),
+      tags: &v.tags,
+    }
+  }
+}
+
+pub fn write(format: Format, sessions: &[Session], out: &mut impl Write) -> io::Result<()> {
+  match format {
+    Format::Csv => csv(sessions, out),
+    Format::Json => {
+      let records: Vec<_> = sessions.iter().map(Record::from).collect();
+      serde_json::to_writer_pretty(&mut *out, &records)?;
+      writeln!(out)
+    }
+    Format::Ics => ics(sessions, out),
+  }
+}
+
+fn csv(sessions: &[Session], out: &mut impl Write) -> io::Result<()> {
+  fn field(v: &str) -> String {
+    match v.contains([',', '"', '\n', '\r']) {
+      true => format!("\"{}\"", v.replace('"', "\"\"")),
+      false => v.to_owned(),
+    }
+  }
+
+  writeln!(out, "start,end,ticker,phase,planned,actual,outcome,task,tags")?;
+  for v in sessions.iter().map(Record::from) {
+    writeln!(
+      out,
+      "{},{},{},{},{},{},{},{},{}",
+      v.start,
+      v.end,
+      field(v.ticker),
+      v.phase,
+      v.planned,
+      v.actual,
+      v.outcome,
+      field(v.task.unwrap_or_default()),
+      field(&v.tags.join(" ")),
+    )?;
+  }
+  Ok(())
+}
+
+/// One VEVENT per work phase, in UTC so calendars show them in their own timezone.
+fn ics(sessions: &[Session], out: &mut impl Write) -> io::Result<()> {
+  const TIME: &str = "%Y%m%dT%H%M%SZ";
+
+  fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\")
+      .replace(';', "\\;")
+      .replace(',', "\\,")
+      .replace("\r\n", "\\n")
+      .replace(['\r', '\n'], "\\n")
+  }
+
+  // content lines are folded after 75 octets
+  fn line(out: &mut impl Write, v: &str) -> io::Result<()> {
+    let mut rest = v;
+    let mut limit = 75;
+    while rest.len() > limit {
+      let mut at = limit;
+      while !rest.is_char_boundary(at) {
+        at -= 1;
+      }
+      write!(out, "{}\r\n ", &rest[..at])?;
+      rest = &rest[at..];
+      // the leading space counts
+      limit = 74;
+    }
+    write!(out, "{rest}\r\n")
+  }
+
+  let stamp = Utc::now().format(TIME);
+
+  line(out, "BEGIN:VCALENDAR")?;
+  line(out, "VERSION:2.0")?;
+  line(out, &format!("PRODID:-//{}//EN", crate::APP_NAME))?;
+
+  for v in sessions.iter().filter(|v| v.phase == Phase::Work) {
+    let start = v.start.with_timezone(&Utc);
+
+    line(out, "BEGIN:VEVENT")?;
+    line(out, &format!("UID:{}-{}@{}", start.timestamp(), v.id, crate::APPID))?;
+    line(out, &format!("DTSTAMP:{stamp}"))?;
+    line(out, &format!("DTSTART:{}", start.format(TIME)))?;
+    line(out, &format!("DTEND:{}", v.end.with_timezone(&Utc).format(TIME)))?;
+    line(out, &format!("SUMMARY:{}", escape(v.task.as_deref().unwrap_or("Focus"))))?;
+    line(out, &format!("DESCRIPTION:{}", escape(v.outcome.name())))?;
+    if !v.tags.is_empty() {
+      let tags: Vec<_> = v.tags.iter().map(|v| escape(v)).collect();
+      line(out, &format!("CATEGORIES:{}", tags.join(",")))?;
+    }
+    line(out, "END:VEVENT")?;
+  }
+
+  line(out, "END:VCALENDAR")
+}
+
+/// Writes the sessions matching `query` to `path`.
+pub fn save(history: &history::History, query: &Query, format: Format, path: &Path) -> Result<(), String> {
+  let sessions = history.sessions(query).map_err(|e| e.to_string())?;
+  save_sessions(format, &sessions, path)
+}
+
+fn save_sessions(format: Format, sessions: &[Session], path: &Path) -> Result<(), String> {
+  let mut out = BufWriter::new(File::create(path).map_err(|e| format!("{}: {e}", path.display()))?);
+  write(format, sessions, &mut out)
+    .and_then(|_| out.flush())
+    .map_err(|e| e.to_string())
+}
+
+/// `timer export`, writes to stdout without `output`.
+pub(crate) fn run(format: Format, from: NaiveDate, to: NaiveDate, output: Option<PathBuf>) -> Result<(), String> {
+  let history = history::open().ok_or("failed to open history")?;
+  let query = Query::days(from, to);
+
+  match output {
+    Some(path) => save(&history, &query, format, &path),
+    None => {
+      let sessions = history.sessions(&query).map_err(|e| e.to_string())?;
+      write(format, &sessions, &mut io::stdout().lock()).map_err(|e| e.to_string())
+    }
+  }
+}
+
+/// Copies the completed work phases matching `query` to `target`, returns how many were not there yet.
+pub fn sync(history: &history::History, config: &UserConfig, target: Target, query: Query) -> Result<usize, String> {
+  copy(config, target, &completed(history, query)?)
+}
+
+fn completed(history: &history::History, query: Query) -> Result<Vec<Session>, String> {
+  Ok(
+    history
+      .sessions(&Query {
+        phase: Some(Phase::Work),
+        ..query
+      })
+      .map_err(|e| e.to_string())?
+      .into_iter()
+      .filter(|v| v.outcome == Outcome::Completed)
+      .collect(),
+  )
+}
+
+/// Writes `sessions` to `target`, returns how many were not there yet.
+fn copy(config: &UserConfig, target: Target, sessions: &[Session]) -> Result<usize, String> {
+  match target {
+    Target::Timewarrior => {
+      let dir = config
+        .timewarrior
+        .as_ref()
+        .and_then(|v| v.data_dir.clone())
+        .or_else(timewarrior::data_dir)
+        .ok_or("no timewarrior data directory")?;
+      timewarrior::sync(&dir, sessions)
+    }
+    Target::Org => {
+      let org = config.org.clone().unwrap_or_default();
+      let file = org.file.unwrap_or_else(|| config::path(ORG_FILE));
+      org::sync(&file, &org.heading, sessions)
+    }
+  }
+  .map_err(|e| format!("{target:?}: {e}"))
+}
+
+/// `timer sync`, the exporters skip what they already have so it can run any number of times.
+pub(crate) fn run_sync(target: Target, from: NaiveDate, to: NaiveDate) -> Result<(), String> {
+  let config = config::config::<UserConfig>().map_err(|e| e.to_string())?;
+  let history = history::open().ok_or("failed to open history")?;
+
+  let written = sync(&history, &config, target, Query::days(from, to))?;
+  println!("{written} new entries");
+  Ok(())
+}
+
+/// Copies the phases of yesterday and today to every configured time tracker, the files are written in the background.
+pub(crate) fn sync_configured(app: &App) -> Task<Message> {
+  let Some(history) = &app.history else {
+    return Task::none();
+  };
+
+  let targets: Vec<_> = [
+    app.config.timewarrior.is_some().then_some(Target::Timewarrior),
+    app.config.org.is_some().then_some(Target::Org),
+  ]
+  .into_iter()
+  .flatten()
+  .collect();
+  if targets.is_empty() {
+    return Task::none();
+  }
+
+  let today = Local::now().date_naive();
+  let sessions = match completed(history, Query::days(today - Days::new(1), today)) {
+    Ok(v) => v,
+    Err(e) => return info::send(e),
+  };
+  let config = UserConfig::clone(&app.config);
+
+  Task::perform(
+    async move {
+      let errors = tokio::task::spawn_blocking(move || {
+        targets
+          .into_iter()
+          .filter_map(|target| copy(&config, target, &sessions).err())
+          .collect::<Vec<_>>()
+      })
+      .await
+      .map_err(|e| e.to_string())?;
+
+      match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("; ")),
+      }
+    },
+    Message::Synced,
+  )
+}
+
+/// Exports what the stats page shows into the downloads directory, the file is written in the background.
+pub(crate) fn export(app: &App, format: Format) -> Task<Message> {
+  let Some(history) = &app.history else {
+    return info::send("no history to export");
+  };
+
+  let sessions = match history.sessions(&app.stats.query(Local::now().date_naive())) {
+    Ok(v) => v,
+    Err(e) => return info::send(format!("failed to export: {e}")),
+  };
+  let name = format!("timer-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), format.extension());
+
+  Task::perform(
+    async move {
+      tokio::task::spawn_blocking(move || {
+        let dir = export_dir().ok_or("no directory to export to")?;
+        let path = dir.join(name);
+        save_sessions(format, &sessions, &path).map(|_| path)
+      })
+      .await
+      .map_err(|e| e.to_string())?
+    },
+    |result| {
+      Message::Info(Info::Send(match result {
+        Ok(path) => format!("exported to {}", path.display()),
+        Err(e) => format!("failed to export: {e}"),
+      }))
+    },
+  )
+}
+
+/// The downloads directory, or the app's data directory without one.
+fn export_dir() -> Option<PathBuf> {
+  if let Some(dir) = dirs::download_dir() {
+    return Some(dir);
+  }
+
+  let dir = dirs::data_dir()?.join(crate::APP_NAME);
+  fs::create_dir_all(&dir).ok()?;
+  Some(dir)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use chrono::TimeZone;
+
+  use super::*;
+  use crate::app::history::Outcome;
+
+  fn sessions() -> Vec<Session> {
+    let start = Utc.with_ymd_and_hms(2024, 2, 14, 9, 0, 0).unwrap().with_timezone(&Local);
+    let session = Session {
+      id: 1,
+      start,
+      end: start + Duration::from_secs(25 * 60),
+      ticker: "Pomodoro".to_owned(),
+      phase: Phase::Work,
+      planned: Duration::from_secs(25 * 60),
+      actual: Duration::from_secs(25 * 60),
+      outcome: Outcome::Completed,
+      task: Some("write, \"report\"".to_owned()),
+      tags: vec!["docs".to_owned()],
+    };
+    let pause = Session {
+      id: 2,
+      phase: Phase::Break,
+      task: None,
+      tags: Vec::new(),
+      ..session.clone()
+    };
+    vec![session, pause]
+  }
+
+  fn export(format: Format) -> String {
+    let mut out = Vec::new();
+    write(format, &sessions(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn csv_quotes_fields() {
+    let csv = export(Format::Csv);
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].ends_with(",work,1500,1500,completed,\"write, \"\"report\"\"\",docs"));
+    assert!(lines[2].ends_with(",break,1500,1500,completed,,"));
+  }
+
+  #[test]
+  fn json_keeps_offset() {
+    let json: serde_json::Value = serde_json::from_str(&export(Format::Json)).unwrap();
+    let start = json[0]["start"].as_str().unwrap();
+    assert_eq!(
+      chrono::DateTime::parse_from_rfc3339(start).unwrap(),
+      Utc.with_ymd_and_hms(2024, 2, 14, 9, 0, 0).unwrap()
+    );
+    assert_eq!(json[1]["phase"], "break");
+  }
+
+  #[test]
+  fn ics_has_one_event_per_work_phase() {
+    let ics = export(Format::Ics);
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(ics.contains("DTSTART:20240214T090000Z\r\n"));
+    assert!(ics.contains("DTEND:20240214T092500Z\r\n"));
+    assert!(ics.contains("SUMMARY:write\\, \"report\"\r\n"));
+
+    let mut session = sessions().remove(0);
+    session.task = Some("line\r\nbreaks\rtoo".to_owned());
+    let mut out = Vec::new();
+    write(Format::Ics, &[session], &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("SUMMARY:line\\nbreaks\\ntoo\r\n"));
+    assert!(ics.lines().all(|v| v.len() <= 76));
+  }
+}
@@ -0,0 +1,192 @@
+use std::{collections::HashSet, fs, io, path::Path};
+
+use crate::app::history::Session;
+
+/// A `CLOCK:` entry with the local start and end of `session`.
+pub fn clock(session: &Session) -> String {
+  const TIME: &str = "%Y-%m-%d %a %H:%M";
+
+  let minutes = session.actual.as_secs() / 60;
+  format!(
+    "CLOCK: [{}]--[{}] => {:2}:{:02}",
+    session.start.format(TIME),
+    session.end.format(TIME),
+    minutes / 60,
+    minutes % 60
+  )
+}
+
+/// Adds the clocks missing from `file` under `heading`, in a subheading per task, returns how many were written.
+pub fn sync(file: &Path, heading: &str, sessions: &[Session]) -> io::Result<usize> {
+  let content = match fs::read_to_string(file) {
+    Ok(v) => v,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+    Err(e) => return Err(e),
+  };
+
+  let mut lines: Vec<String> = content.lines().map(str::to_owned).collect();
+  let written = insert(&mut lines, heading, sessions);
+
+  if written > 0 {
+    // keep the line endings the file was written with
+    let ending = match content.contains("\r\n") {
+      true => "\r\n",
+      false => "\n",
+    };
+    let mut content = lines.join(ending);
+    content.push_str(ending);
+
+    // an editor never sees a half written file
+    let name = file.file_name().map(|v| v.to_string_lossy()).unwrap_or_default();
+    let temp = file.with_file_name(format!(".{name}.tmp"));
+    fs::write(&temp, content)?;
+    fs::rename(&temp, file)?;
+  }
+
+  Ok(written)
+}
+
+fn insert(lines: &mut Vec<String>, heading: &str, sessions: &[Session]) -> usize {
+  let existing: HashSet<String> = lines.iter().map(|v| v.trim().to_owned()).collect();
+  let depth = level(heading).max(1);
+
+  let parent = match lines.iter().position(|v| v.trim_end() == heading.trim_end()) {
+    Some(v) => v,
+    None => {
+      lines.push(heading.trim_end().to_owned());
+      lines.len() - 1
+    }
+  };
+
+  let mut written = 0;
+  for session in sessions {
+    let clock = clock(session);
+    if existing.contains(&clock) {
+      continue;
+    }
+
+    let (at, indent) = match &session.task {
+      Some(task) => {
+        let end = subtree_end(lines, parent, depth);
+        let prefix = format!("{} {task}", "*".repeat(depth + 1));
+        let found = (parent + 1..end).find(|&i| {
+          let line = &lines[i];
+          line.starts_with(&prefix) && line[prefix.len()..].chars().next().is_none_or(char::is_whitespace)
+        });
+
+        let at = found.unwrap_or_else(|| {
+          lines.insert(end, subheading(&prefix, &session.tags));
+          end
+        });
+        (at, depth + 2)
+      }
+      None => (parent, depth + 1),
+    };
+
+    // newest first, like org-clock-in does
+    lines.insert(at + 1, format!("{}{clock}", " ".repeat(indent)));
+    written += 1;
+  }
+
+  written
+}
+
+fn level(line: &str) -> usize {
+  line.chars().take_while(|&c| c == '*').count()
+}
+
+/// Index of the first line after the subtree of the heading at `at`.
+fn subtree_end(lines: &[String], at: usize, depth: usize) -> usize {
+  (at + 1..lines.len())
+    .find(|&i| {
+      let level = level(&lines[i]);
+      (1..=depth).contains(&level) && lines[i][level..].starts_with(' ')
+    })
+    .unwrap_or(lines.len())
+}
+
+fn subheading(prefix: &str, tags: &[String]) -> String {
+  // org tags are made of letters, numbers, `_`, `@`, `#` and `%`
+  let tags: Vec<String> = tags
+    .iter()
+    .map(|v| {
+      v.chars()
+        .map(|c| match c.is_alphanumeric() || "_@#%".contains(c) {
+          true => c,
+          false => '_',
+        })
+        .collect()
+    })
+    .collect();
+
+  match tags.is_empty() {
+    true => prefix.to_owned(),
+    false => format!("{prefix} :{}:", tags.join(":")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use chrono::{Local, TimeZone};
+
+  use super::*;
+  use crate::{
+    app::{history::Outcome, timer::ticker::Phase},
+    util::test::temp_path,
+  };
+
+  fn session(hour: u32, task: Option<&str>) -> Session {
+    let start = Local.with_ymd_and_hms(2024, 2, 14, hour, 0, 0).unwrap();
+    Session {
+      id: 0,
+      start,
+      end: start + Duration::from_secs(25 * 60),
+      ticker: "Pomodoro".to_owned(),
+      phase: Phase::Work,
+      planned: Duration::from_secs(25 * 60),
+      actual: Duration::from_secs(25 * 60),
+      outcome: Outcome::Completed,
+      task: task.map(str::to_owned),
+      tags: vec!["deep work".to_owned()],
+    }
+  }
+
+  #[test]
+  fn clocks_under_task_headings_once() {
+    let mut lines: Vec<String> = ["* Notes", "* Focus", "* Later"].map(str::to_owned).to_vec();
+    let sessions = [session(9, Some("report")), session(10, None), session(11, Some("report"))];
+
+    assert_eq!(insert(&mut lines, "* Focus", &sessions), 3);
+    assert_eq!(
+      lines,
+      [
+        "* Notes",
+        "* Focus",
+        "  CLOCK: [2024-02-14 Wed 10:00]--[2024-02-14 Wed 10:25] =>  0:25",
+        "** report :deep_work:",
+        "   CLOCK: [2024-02-14 Wed 11:00]--[2024-02-14 Wed 11:25] =>  0:25",
+        "   CLOCK: [2024-02-14 Wed 09:00]--[2024-02-14 Wed 09:25] =>  0:25",
+        "* Later",
+      ]
+    );
+
+    assert_eq!(insert(&mut lines, "* Focus", &sessions), 0);
+    assert_eq!(lines.len(), 7);
+  }
+
+  #[test]
+  fn sync_keeps_crlf() {
+    let file = temp_path("org.org");
+    fs::write(&file, "* Notes\r\n* Focus\r\n").unwrap();
+
+    assert_eq!(sync(&file, "* Focus", &[session(9, None)]).unwrap(), 1);
+    assert_eq!(
+      fs::read_to_string(&file).unwrap(),
+      "* Notes\r\n* Focus\r\n  CLOCK: [2024-02-14 Wed 09:00]--[2024-02-14 Wed 09:25] =>  0:25\r\n"
+    );
+
+    fs::remove_file(&file).unwrap();
+  }
+}
@@ -0,0 +1,125 @@
+use std::{
+  collections::{BTreeMap, HashSet},
+  env, fs,
+  io::{self, Write},
+  path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+
+use crate::app::history::Session;
+
+const TIME: &str = "%Y%m%dT%H%M%SZ";
+
+/// `$TIMEWARRIORDB/data`, `~/.timewarrior/data` if it exists, or the XDG data directory.
+pub fn data_dir() -> Option<PathBuf> {
+  if let Some(db) = env::var_os("TIMEWARRIORDB") {
+    return Some(PathBuf::from(db).join("data"));
+  }
+
+  let legacy = dirs::home_dir()?.join(".timewarrior").join("data");
+  match legacy.is_dir() {
+    true => Some(legacy),
+    false => Some(dirs::data_dir()?.join("timewarrior").join("data")),
+  }
+}
+
+/// The interval of `session` as written to a Timewarrior data file.
+pub fn line(session: &Session) -> String {
+  let start = session.start.with_timezone(&Utc).format(TIME);
+  let end = session.end.with_timezone(&Utc).format(TIME);
+
+  let tags: Vec<_> = session.task.iter().chain(&session.tags).map(|v| quote(v)).collect();
+  match tags.is_empty() {
+    true => format!("inc {start} - {end}"),
+    false => format!("inc {start} - {end} # {}", tags.join(" ")),
+  }
+}
+
+/// The `inc <start> - <end>` part of a data line, tags may have been edited since.
+fn interval(line: &str) -> &str {
+  line.split_once(" #").map_or(line, |(v, _)| v).trim()
+}
+
+fn quote(tag: &str) -> String {
+  match tag.contains(|c: char| c.is_whitespace() || c == '"' || c == '#') {
+    true => format!("\"{}\"", tag.replace('"', "\\\"")),
+    false => tag.to_owned(),
+  }
+}
+
+/// Appends the intervals missing from the monthly data files in `dir`, returns how many were written.
+pub fn sync(dir: &Path, sessions: &[Session]) -> io::Result<usize> {
+  let mut files: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  for session in sessions {
+    let name = format!("{}.data", session.start.with_timezone(&Utc).format("%Y-%m"));
+    files.entry(name).or_default().push(line(session));
+  }
+
+  fs::create_dir_all(dir)?;
+
+  let mut written = 0;
+  for (name, lines) in files {
+    let path = dir.join(name);
+    let existing = match fs::read_to_string(&path) {
+      Ok(v) => v,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+      Err(e) => return Err(e),
+    };
+    let existing: HashSet<_> = existing.lines().map(interval).collect();
+
+    let mut file = fs::OpenOptions::new().append(true).create(true).open(&path)?;
+    for line in lines.iter().filter(|v| !existing.contains(interval(v))) {
+      writeln!(file, "{line}")?;
+      written += 1;
+    }
+  }
+
+  Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use chrono::{Local, TimeZone};
+
+  use super::*;
+  use crate::{
+    app::{history::Outcome, timer::ticker::Phase},
+    util::test::temp_path,
+  };
+
+  #[test]
+  fn sync_skips_known_intervals() {
+    let start = Utc.with_ymd_and_hms(2024, 2, 14, 9, 0, 0).unwrap().with_timezone(&Local);
+    let session = Session {
+      id: 0,
+      start,
+      end: start + Duration::from_secs(25 * 60),
+      ticker: "Pomodoro".to_owned(),
+      phase: Phase::Work,
+      planned: Duration::from_secs(25 * 60),
+      actual: Duration::from_secs(25 * 60),
+      outcome: Outcome::Completed,
+      task: Some("write report".to_owned()),
+      tags: vec!["docs".to_owned()],
+    };
+    assert_eq!(
+      line(&session),
+      "inc 20240214T090000Z - 20240214T092500Z # \"write report\" docs"
+    );
+
+    let dir = temp_path("timewarrior");
+
+    assert_eq!(sync(&dir, std::slice::from_ref(&session)).unwrap(), 1);
+    assert_eq!(sync(&dir, std::slice::from_ref(&session)).unwrap(), 0);
+    assert_eq!(fs::read_to_string(dir.join("2024-02.data")).unwrap().lines().count(), 1);
+
+    // retagged in timewarrior, still the same interval
+    fs::write(dir.join("2024-02.data"), "inc 20240214T090000Z - 20240214T092500Z # review\n").unwrap();
+    assert_eq!(sync(&dir, &[session]).unwrap(), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
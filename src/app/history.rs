@@ -0,0 +1,456 @@
+use std::{
+  collections::BTreeSet,
+  fs,
+  path::{Path, PathBuf},
+  sync::mpsc,
+  thread,
+  time::Duration,
+};
+
+use chrono::{DateTime, Days, Local, NaiveDate, TimeZone};
+use iced::Task;
+use rusqlite::{params, Connection, Row};
+use tokio::sync::oneshot;
+
+use super::{config, timer::ticker::Phase, App, Message};
+
+const HISTORY_FILE: &str = "history.sqlite3";
+
+// applied in order, `PRAGMA user_version` is the number already applied
+const MIGRATIONS: &[&str] = &[
+  "CREATE TABLE sessions (
+    id INTEGER PRIMARY KEY,
+    start INTEGER NOT NULL,
+    end INTEGER NOT NULL,
+    ticker TEXT NOT NULL,
+    phase TEXT NOT NULL,
+    planned INTEGER NOT NULL,
+    actual INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    task TEXT
+  );",
+  "CREATE INDEX sessions_start ON sessions (start);",
+  // space separated
+  "ALTER TABLE sessions ADD COLUMN tags TEXT NOT NULL DEFAULT '';",
+];
+
+const COLUMNS: &str = "id, start, end, ticker, phase, planned, actual, outcome, task, tags";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+  Completed,
+  Skipped,
+  // paused, snoozed, restarted or the app was closed
+  Interrupted,
+}
+
+impl Outcome {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Outcome::Completed => "completed",
+      Outcome::Skipped => "skipped",
+      Outcome::Interrupted => "interrupted",
+    }
+  }
+
+  fn from_name(name: &str) -> Option<Self> {
+    [Outcome::Completed, Outcome::Skipped, Outcome::Interrupted]
+      .into_iter()
+      .find(|v| v.name() == name)
+  }
+}
+
+/// A phase that has ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+  pub id: i64,
+  pub start: DateTime<Local>,
+  pub end: DateTime<Local>,
+  pub ticker: String,
+  pub phase: Phase,
+  pub planned: Duration,
+  pub actual: Duration,
+  pub outcome: Outcome,
+  pub task: Option<String>,
+  pub tags: Vec<String>,
+}
+
+/// The phase that is running right now.
+#[derive(Debug, Clone)]
+pub struct Current {
+  pub start: DateTime<Local>,
+  pub ticker: String,
+  pub phase: Phase,
+  pub planned: Duration,
+  pub task: Option<String>,
+  pub tags: Vec<String>,
+}
+
+/// Sessions starting in `[from, to)`, optionally narrowed down.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+  pub from: Option<DateTime<Local>>,
+  pub to: Option<DateTime<Local>>,
+  pub phase: Option<Phase>,
+  pub task: Option<String>,
+  pub tag: Option<String>,
+}
+
+impl Query {
+  /// Sessions starting on the days from `from` to `to`, both included.
+  pub fn days(from: NaiveDate, to: NaiveDate) -> Self {
+    let start_of = |date: NaiveDate| date.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest();
+
+    Self {
+      from: start_of(from),
+      to: to.checked_add_days(Days::new(1)).and_then(start_of),
+      ..Default::default()
+    }
+  }
+}
+
+pub struct History {
+  conn: Connection,
+}
+
+impl History {
+  pub fn open(path: &Path) -> rusqlite::Result<Self> {
+    Self::migrate(Connection::open(path)?)
+  }
+
+  #[cfg(test)]
+  pub fn open_in_memory() -> rusqlite::Result<Self> {
+    Self::migrate(Connection::open_in_memory()?)
+  }
+
+  fn migrate(mut conn: Connection) -> rusqlite::Result<Self> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    let tx = conn.transaction()?;
+    for migration in MIGRATIONS.iter().skip(version) {
+      tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    tx.commit()?;
+
+    Ok(Self { conn })
+  }
+
+  pub fn record(&self, session: &Session) -> rusqlite::Result<i64> {
+    self.conn.execute(
+      "INSERT INTO sessions (start, end, ticker, phase, planned, actual, outcome, task, tags)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+      params![
+        session.start.timestamp(),
+        session.end.timestamp(),
+        session.ticker,
+        session.phase.to_string(),
+        session.planned.as_secs(),
+        session.actual.as_secs(),
+        session.outcome.name(),
+        session.task,
+        session.tags.join(" "),
+      ],
+    )?;
+    Ok(self.conn.last_insert_rowid())
+  }
+
+  /// Matching sessions, oldest first.
+  pub fn sessions(&self, query: &Query) -> rusqlite::Result<Vec<Session>> {
+    let mut stmt = self.conn.prepare(&format!(
+      "SELECT {COLUMNS} FROM sessions
+        WHERE (?1 IS NULL OR start >= ?1) AND (?2 IS NULL OR start < ?2)
+          AND (?3 IS NULL OR phase = ?3) AND (?4 IS NULL OR task = ?4)
+          AND (?5 IS NULL OR ' ' || tags || ' ' LIKE '% ' || ?5 || ' %')
+        ORDER BY start, id"
+    ))?;
+
+    let rows = stmt.query_map(
+      params![
+        query.from.map(|v| v.timestamp()),
+        query.to.map(|v| v.timestamp()),
+        query.phase.map(|v| v.to_string()),
+        query.task,
+        query.tag,
+      ],
+      session,
+    )?;
+    rows.collect()
+  }
+
+  /// Every task label that was recorded, sorted.
+  pub fn tasks(&self) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = self
+      .conn
+      .prepare("SELECT DISTINCT task FROM sessions WHERE task IS NOT NULL ORDER BY task")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+  }
+
+  /// Every tag that was recorded, sorted.
+  pub fn tags(&self) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = self.conn.prepare("SELECT DISTINCT tags FROM sessions WHERE tags != ''")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut tags = BTreeSet::new();
+    for row in rows {
+      tags.extend(row?.split_whitespace().map(str::to_owned));
+    }
+    Ok(tags.into_iter().collect())
+  }
+}
+
+fn session(row: &Row) -> rusqlite::Result<Session> {
+  let time = |i: usize| -> rusqlite::Result<DateTime<Local>> {
+    let secs: i64 = row.get(i)?;
+    Local
+      .timestamp_opt(secs, 0)
+      .single()
+      .ok_or(rusqlite::Error::IntegralValueOutOfRange(i, secs))
+  };
+  let secs = |i: usize| -> rusqlite::Result<Duration> { row.get(i).map(Duration::from_secs) };
+  let invalid = |i: usize, v: String| {
+    rusqlite::Error::FromSqlConversionFailure(i, rusqlite::types::Type::Text, format!("invalid value: {v}").into())
+  };
+
+  let phase: String = row.get(4)?;
+  let outcome: String = row.get(7)?;
+
+  Ok(Session {
+    id: row.get(0)?,
+    start: time(1)?,
+    end: time(2)?,
+    ticker: row.get(3)?,
+    phase: match phase.as_str() {
+      "work" => Phase::Work,
+      "break" => Phase::Break,
+      _ => return Err(invalid(4, phase)),
+    },
+    planned: secs(5)?,
+    actual: secs(6)?,
+    outcome: Outcome::from_name(&outcome).ok_or_else(|| invalid(7, outcome.clone()))?,
+    task: row.get(8)?,
+    tags: row.get::<_, String>(9)?.split_whitespace().map(str::to_owned).collect(),
+  })
+}
+
+enum Write {
+  Record(Session, oneshot::Sender<Result<Session, String>>),
+  Flush(mpsc::Sender<()>),
+}
+
+/// Records sessions in order on a background thread with a connection of its own.
+pub struct Recorder {
+  tx: mpsc::Sender<Write>,
+}
+
+impl Recorder {
+  pub fn new(history: History) -> Self {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+      for write in rx {
+        match write {
+          Write::Record(session, done) => {
+            let result = history
+              .record(&session)
+              .map(|id| Session { id, ..session })
+              .map_err(|e| format!("failed to record session: {e}"));
+            let _ = done.send(result);
+          }
+          Write::Flush(done) => {
+            let _ = done.send(());
+          }
+        }
+      }
+    });
+
+    Self { tx }
+  }
+
+  /// Resolves to the session with its id once it is on disk.
+  pub fn record(&self, session: Session) -> impl std::future::Future<Output = Result<Session, String>> {
+    let (tx, rx) = oneshot::channel();
+    let sent = self.tx.send(Write::Record(session, tx));
+
+    async move {
+      sent.map_err(|_| "history recorder stopped".to_owned())?;
+      rx.await.map_err(|_| "history recorder stopped".to_owned())?
+    }
+  }
+
+  /// Blocks until everything sent so far is on disk.
+  pub fn flush(&self) {
+    let (tx, rx) = mpsc::channel();
+    if self.tx.send(Write::Flush(tx)).is_ok() {
+      let _ = rx.recv();
+    }
+  }
+}
+
+/// The history in the user's data directory, or next to the executable if there is none.
+fn path() -> PathBuf {
+  match dirs::data_dir() {
+    Some(dir) => {
+      let dir = dir.join(crate::APP_NAME);
+      if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("failed to create data directory: {e}");
+      }
+      dir.join(HISTORY_FILE)
+    }
+    None => config::path(HISTORY_FILE),
+  }
+}
+
+/// Opens the history for reading.
+pub(crate) fn open() -> Option<History> {
+  History::open(&path())
+    .inspect_err(|e| eprintln!("failed to open history: {e}"))
+    .ok()
+}
+
+/// Opens the history a second time for the background writes.
+pub(crate) fn recorder() -> Option<Recorder> {
+  open().map(Recorder::new)
+}
+
+/// Starts tracking the current phase unless it is already tracked or the timer is not running.
+pub(crate) fn begin(app: &mut App) {
+  if app.session.is_some() || app.resume.is_some() {
+    return;
+  }
+  let Some(planned) = app.timer.deadline().and(app.timer.remaining()) else {
+    return;
+  };
+
+  app.session = Some(Current {
+    start: Local::now(),
+    ticker: app.timer.ticker.name.to_owned(),
+    phase: app.timer.phase(),
+    planned,
+    task: app.tasks.active().map(|v| v.title.clone()),
+    tags: app.tasks.active().map(|v| v.tags.clone()).unwrap_or_default(),
+  });
+}
+
+/// Records the tracked phase as ended now, [`Message::Recorded`] follows once it is written.
+pub(crate) fn finish(app: &mut App, outcome: Outcome) -> Task<Message> {
+  let Some(current) = app.session.take() else {
+    return Task::none();
+  };
+
+  let end = Local::now();
+  let session = Session {
+    id: 0,
+    actual: (end - current.start).to_std().unwrap_or_default(),
+    start: current.start,
+    end,
+    ticker: current.ticker,
+    phase: current.phase,
+    planned: current.planned,
+    outcome,
+    task: current.task,
+    tags: current.tags,
+  };
+
+  match &app.recorder {
+    Some(recorder) => Task::perform(recorder.record(session), Message::Recorded),
+    None => Task::none(),
+  }
+}
+
+/// Ends the tracked phase and starts tracking the one that follows.
+pub(crate) fn transition(app: &mut App, outcome: Outcome) -> Task<Message> {
+  let recorded = finish(app, outcome);
+  begin(app);
+  recorded
+}
+
+/// Waits for the sessions still being written, before the app exits.
+pub(crate) fn close(app: &App) {
+  if let Some(recorder) = &app.recorder {
+    recorder.flush();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::test::{block_on, temp_path};
+
+  fn session(start: i64, phase: Phase, task: Option<&str>) -> Session {
+    let start = Local.timestamp_opt(start, 0).unwrap();
+    Session {
+      id: 0,
+      start,
+      end: start + Duration::from_secs(60),
+      ticker: "Pomodoro".to_owned(),
+      phase,
+      planned: Duration::from_secs(60),
+      actual: Duration::from_secs(60),
+      outcome: Outcome::Completed,
+      task: task.map(str::to_owned),
+      tags: task.map(|v| vec![v.to_owned(), "desk".to_owned()]).unwrap_or_default(),
+    }
+  }
+
+  #[test]
+  fn record_and_query() {
+    let history = History::open_in_memory().unwrap();
+
+    history.record(&session(100, Phase::Work, Some("write"))).unwrap();
+    history.record(&session(200, Phase::Break, None)).unwrap();
+    let id = history.record(&session(300, Phase::Work, Some("read"))).unwrap();
+
+    let all = history.sessions(&Query::default()).unwrap();
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[2].id, id);
+    assert_eq!(all[2].task.as_deref(), Some("read"));
+
+    let work = history
+      .sessions(&Query {
+        from: Some(Local.timestamp_opt(150, 0).unwrap()),
+        phase: Some(Phase::Work),
+        ..Default::default()
+      })
+      .unwrap();
+    assert_eq!(work.iter().map(|v| v.start.timestamp()).collect::<Vec<_>>(), vec![300]);
+
+    assert_eq!(history.tasks().unwrap(), vec!["read", "write"]);
+    assert_eq!(history.tags().unwrap(), vec!["desk", "read", "write"]);
+
+    let tagged = history
+      .sessions(&Query {
+        tag: Some("read".to_owned()),
+        ..Default::default()
+      })
+      .unwrap();
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].tags, vec!["read", "desk"]);
+  }
+
+  #[test]
+  fn recorded_in_the_background() {
+    let path = temp_path("history-recorder");
+
+    let recorder = Recorder::new(History::open(&path).unwrap());
+    let recorded = block_on(recorder.record(session(100, Phase::Work, Some("write")))).unwrap();
+
+    let history = History::open(&path).unwrap();
+    assert_eq!(history.sessions(&Query::default()).unwrap(), vec![recorded]);
+
+    drop(recorder);
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn migrations_are_applied_once() {
+    let path = temp_path("history");
+
+    History::open(&path).unwrap().record(&session(100, Phase::Work, None)).unwrap();
+    let history = History::open(&path).unwrap();
+    assert_eq!(history.sessions(&Query::default()).unwrap().len(), 1);
+
+    std::fs::remove_file(&path).unwrap();
+  }
+}
@@ -0,0 +1,150 @@
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use iced::Task;
+use tokio::{io::AsyncWriteExt, process::Command, sync::Semaphore};
+
+use super::{config::HookConfig, event::Event, App, Message};
+use crate::util::process;
+
+/// Runs hooks with at most `limit` at a time.
+#[derive(Clone)]
+pub struct Hooks {
+  semaphore: Arc<Semaphore>,
+}
+
+impl Hooks {
+  pub fn new(limit: usize) -> Self {
+    Self {
+      semaphore: Arc::new(Semaphore::new(limit.max(1))),
+    }
+  }
+}
+
+pub(crate) fn run(app: &App, event: &Event) -> Task<Message> {
+  Task::batch(
+    app
+      .config
+      .hooks
+      .iter()
+      .filter(|hook| hook.event == event.event)
+      .map(|hook| {
+        Task::perform(
+          execute(app.hooks.clone(), hook.clone(), event.clone()),
+          Message::HookFinished,
+        )
+      }),
+  )
+}
+
+/// Runs `hook.command` through the shell, with the event in the environment and as JSON on stdin.
+pub async fn execute(hooks: Hooks, hook: HookConfig, event: Event) -> Result<(), String> {
+  let _permit = hooks.semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+
+  let json = serde_json::to_vec(&event).map_err(|e| e.to_string())?;
+
+  let mut shell = shell(&hook.command);
+  // a timed out hook is killed along with everything it started
+  process::new_group(shell.as_std_mut());
+
+  let mut child = shell
+    .env("TIMER_EVENT", event.event.name())
+    .env("TIMER_TICKER", &event.ticker)
+    .env("TIMER_PHASE", event.phase.to_string())
+    .env("TIMER_NEXT_PHASE", event.next_phase.to_string())
+    .env("TIMER_CYCLE", event.cycle.to_string())
+    .env(
+      "TIMER_REMAINING",
+      event.remaining.map(|v| v.to_string()).unwrap_or_default(),
+    )
+    .env("TIMER_PAUSED", event.paused.to_string())
+    .stdin(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()
+    .map_err(|e| format!("hook `{}`: {e}", hook.command))?;
+  let pid = child.id();
+
+  let timeout: Duration = (&hook.timeout).into();
+  let result = tokio::time::timeout(timeout, async {
+    if let Some(mut stdin) = child.stdin.take() {
+      // the hook may not read stdin at all
+      let _ = stdin.write_all(&json).await;
+    }
+    child.wait().await
+  })
+  .await;
+
+  match result {
+    Ok(Ok(status)) if status.success() => Ok(()),
+    Ok(Ok(status)) => Err(format!("hook `{}`: {status}", hook.command)),
+    Ok(Err(e)) => Err(format!("hook `{}`: {e}", hook.command)),
+    Err(_) => {
+      if let Some(pid) = pid {
+        process::kill_group(pid);
+      }
+      Err(format!("hook `{}` timed out", hook.command))
+    }
+  }
+}
+
+fn shell(command: &str) -> Command {
+  #[cfg(windows)]
+  {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+  }
+  #[cfg(not(windows))]
+  {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+  }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+  use crate::{
+    app::{config::Hms, event::EventKind, timer::Timer},
+    util::test::{block_on, temp_path},
+  };
+
+  fn hook(command: String, timeout: Hms) -> HookConfig {
+    HookConfig {
+      event: EventKind::WorkEnd,
+      command,
+      timeout,
+    }
+  }
+
+  #[test]
+  fn event_passed_to_hook() {
+    let out = temp_path("hook");
+    let command = format!("echo $TIMER_EVENT > {0}; cat >> {0}", out.display());
+    let event = Event::new(EventKind::WorkEnd, &Timer::default());
+
+    block_on(execute(Hooks::new(1), hook(command, Hms::ZERO.second(5)), event.clone())).unwrap();
+
+    let written = std::fs::read_to_string(&out).unwrap();
+    let (name, json) = written.split_once('\n').unwrap();
+    assert_eq!(name, "work_end");
+    assert_eq!(serde_json::from_str::<Event>(json).unwrap(), event);
+
+    std::fs::remove_file(&out).unwrap();
+  }
+
+  #[test]
+  fn timeout_and_failure() {
+    let event = Event::new(EventKind::WorkEnd, &Timer::default());
+
+    // the background job would still write after the hook was killed if only `sh` went down
+    let out = temp_path("hook-orphan");
+    let slow = hook(format!("(sleep 2; touch {}) & sleep 5", out.display()), Hms::ZERO.second(1));
+    assert!(block_on(execute(Hooks::new(1), slow, event.clone())).is_err_and(|e| e.ends_with("timed out")));
+    std::thread::sleep(Duration::from_secs(2));
+    assert!(!out.exists());
+
+    let failing = hook("exit 3".to_owned(), Hms::ZERO.second(5));
+    assert!(block_on(execute(Hooks::new(1), failing, event)).is_err());
+  }
+}
@@ -0,0 +1,150 @@
+use std::{
+  fs::{File, OpenOptions},
+  io::{self, Read, Write},
+  path::Path,
+};
+
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use super::{config, timer::TimerState};
+
+const JOURNAL_FILE: &str = "timer.journal";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEvent {
+  Start,
+  Pause,
+  Resume,
+  Phase,
+  Restore,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Entry {
+  pub at: NaiveDateTime,
+  pub event: JournalEvent,
+  pub state: TimerState,
+}
+
+/// Result of replaying a journal file.
+#[derive(Debug, Default)]
+pub struct Replay {
+  pub state: Option<TimerState>,
+  pub entries: usize,
+  // bytes dropped from a corrupt tail
+  pub dropped: usize,
+}
+
+/// Append-only log of timer transitions, one JSON entry per line.
+pub struct Journal {
+  file: File,
+}
+
+impl Journal {
+  /// Replays `path` and opens it for appending, cutting off a corrupt tail if there is one.
+  pub fn recover(path: &Path) -> io::Result<(Self, Replay)> {
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+
+    let (replay, valid) = replay(&buf);
+    if replay.dropped > 0 {
+      file.set_len(valid as u64)?;
+      file.sync_all()?;
+    }
+
+    Ok((Self { file }, replay))
+  }
+
+  pub fn append(&mut self, event: JournalEvent, state: TimerState) -> io::Result<()> {
+    let entry = Entry {
+      at: Local::now().naive_local(),
+      event,
+      state,
+    };
+
+    let mut line = serde_json::to_vec(&entry).map_err(io::Error::other)?;
+    line.push(b'\n');
+    self.file.write_all(&line)?;
+
+    if event == JournalEvent::Phase {
+      self.file.sync_data()?;
+    }
+
+    Ok(())
+  }
+
+  pub fn clear(&mut self) -> io::Result<()> {
+    self.file.set_len(0)?;
+    self.file.sync_all()
+  }
+}
+
+pub(crate) fn open() -> Option<(Journal, Replay)> {
+  Journal::recover(&config::path(JOURNAL_FILE))
+    .inspect_err(|e| eprintln!("failed to open journal: {e}"))
+    .ok()
+}
+
+/// Returns the replayed state and the length of the valid prefix of `buf`.
+fn replay(buf: &[u8]) -> (Replay, usize) {
+  let mut replay = Replay::default();
+  let mut valid = 0;
+
+  for line in buf.split_inclusive(|&b| b == b'\n') {
+    // an unterminated line was cut off while writing
+    if !line.ends_with(b"\n") {
+      break;
+    }
+
+    match serde_json::from_slice::<Entry>(line) {
+      Ok(entry) => {
+        replay.state = Some(entry.state);
+        replay.entries += 1;
+        valid += line.len();
+      }
+      Err(_) => break,
+    }
+  }
+
+  replay.dropped = buf.len() - valid;
+  (replay, valid)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{app::timer::Timer, util::test::temp_path};
+
+  #[test]
+  fn corrupt_tail() {
+    let path = temp_path("journal");
+
+    let mut timer = Timer::default();
+    {
+      let (mut journal, replay) = Journal::recover(&path).unwrap();
+      assert!(replay.state.is_none());
+
+      journal.append(JournalEvent::Start, timer.snapshot()).unwrap();
+      timer.cycle = 3;
+      journal.append(JournalEvent::Phase, timer.snapshot()).unwrap();
+    }
+
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"{\"at\":\"2025-01").unwrap();
+    drop(file);
+
+    let (_, replay) = Journal::recover(&path).unwrap();
+    assert_eq!(replay.entries, 2);
+    assert!(replay.dropped > 0);
+    assert_eq!(replay.state.map(|v| v.cycle), Some(3));
+
+    let (_, replay) = Journal::recover(&path).unwrap();
+    assert_eq!(replay.dropped, 0);
+
+    std::fs::remove_file(&path).unwrap();
+  }
+}
@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use iced::{
+  futures::{SinkExt, Stream},
+  stream, Subscription, Task,
+};
+use rumqttc::{AsyncClient, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use serde::Serialize;
+
+use super::{
+  config::MqttConfig,
+  event::Event,
+  timer::{ticker::Phase, Timer},
+  App, Message,
+};
+
+/// Commands accepted on the command topic, as plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+  Pause,
+  Resume,
+  Skip,
+  Snooze,
+}
+
+impl Command {
+  pub fn parse(payload: &[u8]) -> Option<Command> {
+    let payload = std::str::from_utf8(payload).ok()?.trim().to_ascii_lowercase();
+    Some(match payload.as_str() {
+      "pause" => Command::Pause,
+      "resume" => Command::Resume,
+      "skip" => Command::Skip,
+      "snooze" => Command::Snooze,
+      _ => return None,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum MqttEvent {
+  // sent again after every reconnect
+  Connected(AsyncClient),
+  Command(Command),
+  Error(String),
+}
+
+/// Retained on the state topic.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct State {
+  pub online: bool,
+  pub ticker: String,
+  pub phase: Phase,
+  pub remaining: Option<u64>,
+  pub paused: bool,
+}
+
+impl State {
+  pub fn new(timer: &Timer) -> Self {
+    Self {
+      online: true,
+      ticker: timer.ticker.name.to_owned(),
+      phase: timer.phase(),
+      remaining: timer.remaining().map(|v| v.as_secs()),
+      paused: !timer.enable,
+    }
+  }
+}
+
+const OFFLINE: &str = r#"{"online":false}"#;
+
+fn options(config: &MqttConfig) -> MqttOptions {
+  let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+  options.set_keep_alive(Duration::from_secs(30));
+  options.set_last_will(LastWill::new(&config.state_topic, OFFLINE, QoS::AtLeastOnce, true));
+  if let Some(username) = &config.username {
+    options.set_credentials(username, config.password.clone().unwrap_or_default());
+  }
+  options
+}
+
+/// Keeps the connection alive and forwards commands, reconnecting after errors.
+pub fn listen(config: MqttConfig) -> Subscription<MqttEvent> {
+  let id = format!("{}:{}/{}", config.host, config.port, config.command_topic);
+  Subscription::run_with_id(id, connection(config))
+}
+
+fn connection(config: MqttConfig) -> impl Stream<Item = MqttEvent> {
+  const CONNECTION: usize = 8;
+  const RETRY: Duration = Duration::from_secs(5);
+
+  stream::channel(CONNECTION, move |mut output| async move {
+    let (client, mut eventloop): (AsyncClient, EventLoop) = AsyncClient::new(options(&config), 16);
+
+    loop {
+      match eventloop.poll().await {
+        Ok(rumqttc::Event::Incoming(Packet::ConnAck(_))) => {
+          // subscriptions do not survive a reconnect with a clean session
+          if let Err(e) = client.subscribe(&config.command_topic, QoS::AtLeastOnce).await {
+            let _ = output.send(MqttEvent::Error(e.to_string())).await;
+          }
+          // the broker published the last will when the connection dropped, so the state is sent again
+          if output.send(MqttEvent::Connected(client.clone())).await.is_err() {
+            break;
+          }
+        }
+        Ok(rumqttc::Event::Incoming(Packet::Publish(publish))) if publish.topic == config.command_topic => {
+          let event = match Command::parse(&publish.payload) {
+            Some(command) => MqttEvent::Command(command),
+            None => MqttEvent::Error(format!("unknown mqtt command: {:?}", publish.payload)),
+          };
+          if output.send(event).await.is_err() {
+            break;
+          }
+        }
+        Ok(_) => (),
+        Err(e) => {
+          let _ = output.send(MqttEvent::Error(e.to_string())).await;
+          tokio::time::sleep(RETRY).await;
+        }
+      }
+    }
+  })
+}
+
+/// Publishes the retained state and, for events, the event itself.
+pub(crate) fn publish(app: &App, event: Option<&Event>) -> Task<Message> {
+  let (Some(client), Some(config)) = (app.mqtt.clone(), app.config.mqtt.clone()) else {
+    return Task::none();
+  };
+
+  let state = serde_json::to_vec(&State::new(&app.timer)).unwrap_or_default();
+  let event = event.and_then(|v| serde_json::to_vec(v).ok());
+
+  Task::perform(
+    async move {
+      client
+        .publish(&config.state_topic, QoS::AtLeastOnce, true, state)
+        .await
+        .map_err(|e| e.to_string())?;
+      if let Some(event) = event {
+        client
+          .publish(&config.event_topic, QoS::AtLeastOnce, false, event)
+          .await
+          .map_err(|e| e.to_string())?;
+      }
+      Ok(())
+    },
+    Message::MqttPublished,
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::test::block_on;
+
+  #[test]
+  fn parse_command() {
+    assert_eq!(Command::parse(b"pause"), Some(Command::Pause));
+    assert_eq!(Command::parse(b" Resume\n"), Some(Command::Resume));
+    assert_eq!(Command::parse(b"skip"), Some(Command::Skip));
+    assert_eq!(Command::parse(b"stop"), None);
+    assert_eq!(Command::parse(&[0xff]), None);
+  }
+
+  /// Publishes a command and reads back the retained state through a local broker such as mosquitto.
+  #[test]
+  #[ignore = "needs a broker, set MQTT_TEST_BROKER=host:port"]
+  fn local_broker() {
+    let broker = std::env::var("MQTT_TEST_BROKER").unwrap();
+    let (host, port) = broker.rsplit_once(':').unwrap();
+    let config = MqttConfig {
+      host: host.to_owned(),
+      port: port.parse().unwrap(),
+      client_id: format!("timer-test-{}", std::process::id()),
+      state_topic: format!("timer-test/{}/state", std::process::id()),
+      event_topic: format!("timer-test/{}/event", std::process::id()),
+      command_topic: format!("timer-test/{}/command", std::process::id()),
+      ..Default::default()
+    };
+
+    block_on(async {
+      use iced::futures::StreamExt;
+
+      let mut events = Box::pin(connection(config.clone()));
+      let Some(MqttEvent::Connected(client)) = events.next().await else {
+        panic!("no client");
+      };
+
+      // drive the connection until it subscribed to the command topic
+      let _ = tokio::time::timeout(Duration::from_millis(500), events.next()).await;
+      client
+        .publish(&config.command_topic, QoS::AtLeastOnce, false, "skip")
+        .await
+        .unwrap();
+
+      let command = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+          match events.next().await {
+            Some(MqttEvent::Command(command)) => break command,
+            Some(_) => continue,
+            None => panic!("connection closed"),
+          }
+        }
+      })
+      .await
+      .unwrap();
+      assert_eq!(command, Command::Skip);
+
+      let state = serde_json::to_vec(&State::new(&Timer::default())).unwrap();
+      client
+        .publish(&config.state_topic, QoS::AtLeastOnce, true, state.clone())
+        .await
+        .unwrap();
+
+      // a second client gets the retained state right after subscribing
+      let (reader, mut eventloop) = AsyncClient::new(
+        MqttOptions::new(format!("{}-reader", config.client_id), &config.host, config.port),
+        16,
+      );
+      reader.subscribe(&config.state_topic, QoS::AtLeastOnce).await.unwrap();
+      let retained = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+          if let Ok(rumqttc::Event::Incoming(Packet::Publish(publish))) = eventloop.poll().await {
+            break publish;
+          }
+        }
+      })
+      .await
+      .unwrap();
+      assert!(retained.retain);
+      assert_eq!(retained.payload.as_ref(), state.as_slice());
+    });
+  }
+}
@@ -0,0 +1,330 @@
+pub mod action;
+mod banner;
+mod command;
+mod desktop;
+mod log_file;
+mod print;
+mod speech;
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use action::Action;
+use iced::{futures::future, Task};
+
+use super::{
+  config::{self, Backend, NotificationConfig, UserConfig},
+  template::{self, Vars},
+  timer::TimerEvent,
+  App, Message,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const LOG_FILE: &str = "timer.log";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryError {
+  Failed(String),
+  TimedOut,
+}
+
+impl fmt::Display for DeliveryError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DeliveryError::Failed(e) => write!(f, "notification failed: {e}"),
+      DeliveryError::TimedOut => write!(f, "notification timed out"),
+    }
+  }
+}
+
+/// A single way of telling the user that something happened.
+pub trait Notifier: Send + Sync {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+  Sent,
+  // the app has to show it itself
+  Banner,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Notice {
+  pub summary: String,
+  pub body: String,
+  pub actions: Vec<(Action, String)>,
+  pub urgency: Urgency,
+  // takes the place of the previous notice sent with it, so repetitions do not pile up
+  pub replace: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+  Low,
+  #[default]
+  Normal,
+  Critical,
+}
+
+impl Urgency {
+  /// Urgency of the `n`th repetition of an escalating notice.
+  pub fn escalated(n: u32) -> Self {
+    match n {
+      0 => Urgency::Low,
+      1 => Urgency::Normal,
+      _ => Urgency::Critical,
+    }
+  }
+}
+
+impl Notice {
+  /// Renders the template of `event` at fire time.
+  pub fn new(config: &UserConfig, vars: &Vars, event: TimerEvent) -> Self {
+    let snooze = config.snooze.as_minutes();
+    let config = &config.notification;
+
+    let (summary, body) = match config.templates.get(event) {
+      Some(template) => (&template.summary, &template.body),
+      None => (&config.desktop.summary, &config.desktop.body),
+    };
+
+    let actions = match (config.actions, event) {
+      (true, TimerEvent::WorkEnd | TimerEvent::BreakEnd) => vec![
+        (Action::Snooze, format!("Snooze {snooze} min")),
+        (Action::Skip, format!("Skip {}", vars.next_phase)),
+        (Action::Next, format!("Start {}", vars.next_phase)),
+      ],
+      _ => Vec::new(),
+    };
+
+    let mut rendered = template::render(body, vars);
+    // the suggested activity goes below the text, unless the template already places it
+    let placed = [summary, body].iter().any(|v| v.contains("{activity}"));
+    if !vars.activity.is_empty() && !placed {
+      if !rendered.is_empty() {
+        rendered.push('\n');
+      }
+      rendered.push_str(&vars.activity);
+    }
+
+    Self {
+      summary: template::render(summary, vars),
+      body: rendered,
+      actions,
+      urgency: Urgency::default(),
+      replace: false,
+    }
+  }
+}
+
+impl fmt::Display for Notice {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.body.is_empty() {
+      true => write!(f, "{}", self.summary),
+      false => write!(f, "{}: {}", self.summary, self.body),
+    }
+  }
+}
+
+/// Outcome of sending a notice through every configured backend.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+  pub banner: Option<String>,
+  pub errors: Vec<String>,
+}
+
+/// A backend followed by the backends tried when it fails, in order.
+type Chain = Vec<(Backend, Arc<dyn Notifier>)>;
+
+#[derive(Clone, Default)]
+pub struct Notifiers {
+  chains: Arc<Vec<Chain>>,
+}
+
+impl Notifiers {
+  pub fn new(config: &NotificationConfig) -> Self {
+    let chains = config
+      .backends
+      .iter()
+      .map(|v| {
+        std::iter::once(v.backend)
+          .chain(v.fallback.iter().copied())
+          .map(|backend| (backend, notifier(config, backend)))
+          .collect()
+      })
+      .collect();
+
+    Self {
+      chains: Arc::new(chains),
+    }
+  }
+}
+
+fn notifier(config: &NotificationConfig, backend: Backend) -> Arc<dyn Notifier> {
+  match backend {
+    Backend::Desktop => Arc::new(desktop::Desktop::new(config.desktop.clone())),
+    Backend::Stdout => Arc::new(print::Stdout),
+    Backend::Stderr => Arc::new(print::Stderr),
+    Backend::LogFile => Arc::new(log_file::LogFile {
+      path: config.log_file.clone().unwrap_or_else(|| config::path(LOG_FILE)),
+    }),
+    Backend::Banner => Arc::new(banner::Banner),
+    Backend::Command => Arc::new(command::Command {
+      command: config.command.clone(),
+    }),
+    Backend::Speech => Arc::new(speech::Speech::new(config.speech.command.clone(), config.speech.mode)),
+  }
+}
+
+/// Notifies about `event`, `escalation` being the repetition count of an escalating notice.
+pub(crate) fn notify(app: &App, event: TimerEvent, escalation: Option<u32>) -> Task<Message> {
+  let mut vars = Vars::new(&app.timer, event);
+  vars.activity = app.activity.as_ref().map(|v| v.title.clone()).unwrap_or_default();
+  vars.task = app.tasks.active().map(|v| v.title.clone()).unwrap_or_default();
+
+  let mut notice = Notice::new(&app.config, &vars, event);
+  if let Some(n) = escalation {
+    notice.urgency = Urgency::escalated(n);
+    notice.replace = true;
+  }
+
+  self::notice(app, notice)
+}
+
+/// Sends a notice that is not tied to a timer event.
+pub(crate) fn notice(app: &App, notice: Notice) -> Task<Message> {
+  Task::perform(send(app.notifiers.clone(), notice), Message::Notified)
+}
+
+/// Sends `notice` through every chain at once, moving on to a chain's fallback when a backend fails.
+pub async fn send(notifiers: Notifiers, notice: Notice) -> Report {
+  let notice = Arc::new(notice);
+
+  let results = future::join_all(notifiers.chains.iter().cloned().map(|chain| {
+    let notice = notice.clone();
+    async move {
+      let mut errors = Vec::new();
+      for (backend, notifier) in chain {
+        let notice = notice.clone();
+        match deliver(move || notifier.notify(&notice), TIMEOUT).await {
+          Ok(delivery) => return (Some(delivery), errors),
+          Err(e) => errors.push(format!("{backend:?}: {e}")),
+        }
+      }
+      (None, errors)
+    }
+  }))
+  .await;
+
+  let mut report = Report::default();
+  for (delivery, errors) in results {
+    if delivery == Some(Delivery::Banner) {
+      report.banner = Some(notice.to_string());
+    }
+    report.errors.extend(errors);
+  }
+  report
+}
+
+/// Runs the blocking `show` off the UI thread, giving up after `timeout`.
+pub async fn deliver<T, F>(show: F, timeout: Duration) -> Result<T, DeliveryError>
+where
+  T: Send + 'static,
+  F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+  match tokio::time::timeout(timeout, tokio::task::spawn_blocking(show)).await {
+    Ok(Ok(result)) => result.map_err(DeliveryError::Failed),
+    Ok(Err(e)) => Err(DeliveryError::Failed(e.to_string())),
+    Err(_) => Err(DeliveryError::TimedOut),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{sync::mpsc, thread};
+
+  use super::*;
+  use crate::util::test::block_on;
+
+  // stand-in for a notification daemon that answers after `delay`
+  fn server(delay: Duration, response: Result<(), String>) -> impl FnOnce() -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+      thread::sleep(delay);
+      let _ = tx.send(response);
+    });
+    move || rx.recv().map_err(|e| e.to_string())?
+  }
+
+  #[test]
+  fn delivered() {
+    let show = server(Duration::ZERO, Ok(()));
+    assert_eq!(block_on(deliver(show, Duration::from_secs(1))), Ok(()));
+  }
+
+  #[test]
+  fn failed() {
+    let show = server(Duration::ZERO, Err("no daemon".to_owned()));
+    assert_eq!(
+      block_on(deliver(show, Duration::from_secs(1))),
+      Err(DeliveryError::Failed("no daemon".to_owned()))
+    );
+  }
+
+  #[test]
+  fn timed_out() {
+    let show = server(Duration::from_millis(200), Ok(()));
+    assert_eq!(
+      block_on(deliver(show, Duration::from_millis(20))),
+      Err(DeliveryError::TimedOut)
+    );
+  }
+
+  struct Failing;
+
+  impl Notifier for Failing {
+    fn notify(&self, _notice: &Notice) -> Result<Delivery, String> {
+      Err("unavailable".to_owned())
+    }
+  }
+
+  #[test]
+  fn fallback_order() {
+    let failing: Arc<dyn Notifier> = Arc::new(Failing);
+    let notifiers = Notifiers {
+      chains: Arc::new(vec![
+        vec![(Backend::Desktop, failing.clone()), (Backend::Banner, Arc::new(banner::Banner))],
+        vec![(Backend::Command, failing)],
+      ]),
+    };
+    let notice = Notice {
+      summary: "Elapsed now".to_owned(),
+      ..Default::default()
+    };
+
+    let report = block_on(send(notifiers, notice));
+    assert_eq!(report.banner.as_deref(), Some("Elapsed now"));
+    assert_eq!(report.errors.len(), 2);
+  }
+
+  #[test]
+  fn activity_below_the_text() {
+    let mut config = UserConfig::default();
+    let vars = Vars {
+      activity: "Stretch".to_owned(),
+      ..Default::default()
+    };
+
+    let notice = Notice::new(&config, &vars, TimerEvent::WorkEnd);
+    assert_eq!((notice.summary.as_str(), notice.body.as_str()), ("Elapsed now", "Stretch"));
+
+    config.notification.templates.work_end = Some(config::Template {
+      summary: "Done".to_owned(),
+      body: "next: {activity}".to_owned(),
+    });
+    assert_eq!(Notice::new(&config, &vars, TimerEvent::WorkEnd).body, "next: Stretch");
+
+    let notice = Notice::new(&config, &Vars::default(), TimerEvent::WorkEnd);
+    assert_eq!(notice.body, "next: ");
+  }
+}
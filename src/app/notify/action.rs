@@ -0,0 +1,44 @@
+use std::sync::{
+  mpsc::{self, Receiver, Sender},
+  LazyLock, Mutex,
+};
+
+/// Buttons on a notification that route back into the timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  Snooze,
+  // skip the phase that just started
+  Skip,
+  // start the phase that just started over from now
+  Next,
+}
+
+impl Action {
+  pub const ALL: &[Action] = &[Action::Snooze, Action::Skip, Action::Next];
+
+  pub fn id(&self) -> &'static str {
+    match self {
+      Action::Snooze => "snooze",
+      Action::Skip => "skip",
+      Action::Next => "next",
+    }
+  }
+
+  pub fn from_id(id: &str) -> Option<Action> {
+    Self::ALL.iter().find(|v| v.id() == id).copied()
+  }
+}
+
+static CHANNEL: LazyLock<(Sender<Action>, Mutex<Receiver<Action>>)> = LazyLock::new(|| {
+  let (tx, rx) = mpsc::channel();
+  (tx, Mutex::new(rx))
+});
+
+/// Invoked actions are sent here, like the tray events of `tray_icon`.
+pub fn sender() -> Sender<Action> {
+  CHANNEL.0.clone()
+}
+
+pub fn receiver() -> &'static Mutex<Receiver<Action>> {
+  &CHANNEL.1
+}
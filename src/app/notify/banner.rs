@@ -0,0 +1,10 @@
+use super::*;
+
+/// Shown inside the app, so there is nothing to deliver here.
+pub struct Banner;
+
+impl Notifier for Banner {
+  fn notify(&self, _notice: &Notice) -> Result<Delivery, String> {
+    Ok(Delivery::Banner)
+  }
+}
@@ -0,0 +1,45 @@
+use std::{
+  process, thread,
+  time::{Duration, Instant},
+};
+
+use super::*;
+use crate::util::process::{kill_group, new_group};
+
+/// Runs `program args.. summary body`.
+pub struct Command {
+  pub command: Vec<String>,
+}
+
+impl Notifier for Command {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String> {
+    const POLL: Duration = Duration::from_millis(50);
+
+    let (program, args) = self.command.split_first().ok_or("no command configured")?;
+
+    let mut child = new_group(process::Command::new(program).args(args))
+      .arg(&notice.summary)
+      .arg(&notice.body)
+      .spawn()
+      .map_err(|e| format!("{program}: {e}"))?;
+
+    // the delivery gives up after `TIMEOUT`, a stuck command goes down with whatever it started
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+      match child.try_wait().map_err(|e| format!("{program}: {e}"))? {
+        Some(status) => break status,
+        None if Instant::now() >= deadline => {
+          kill_group(child.id());
+          let _ = child.wait();
+          return Err(format!("{program}: timed out"));
+        }
+        None => thread::sleep(POLL),
+      }
+    };
+
+    if !status.success() {
+      return Err(format!("{program}: {status}"));
+    }
+    Ok(Delivery::Sent)
+  }
+}
@@ -0,0 +1,172 @@
+use std::sync::Mutex;
+
+use notify_rust::Notification;
+
+use super::*;
+use crate::app::config::NotificationLike;
+
+pub struct Desktop {
+  pub notification: NotificationLike,
+  // id of the last notification a notice with `replace` went to
+  pub replaced: Mutex<Option<u32>>,
+}
+
+impl Desktop {
+  pub fn new(notification: NotificationLike) -> Self {
+    Self {
+      notification,
+      replaced: Mutex::new(None),
+    }
+  }
+}
+
+impl Notifier for Desktop {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String> {
+    let mut notification: Notification = self.notification.clone().into();
+    notification.summary = notice.summary.clone();
+    notification.body = notice.body.clone();
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+      for (action, label) in &notice.actions {
+        notification.action(action.id(), label);
+      }
+
+      notification.urgency(match notice.urgency {
+        Urgency::Low => notify_rust::Urgency::Low,
+        Urgency::Normal => notify_rust::Urgency::Normal,
+        Urgency::Critical => notify_rust::Urgency::Critical,
+      });
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let previous = match notice.replace {
+      true => {
+        let last = *self.replaced.lock().map_err(|e| e.to_string())?;
+        if let Some(id) = last {
+          notification.id(id);
+        }
+        Some(last)
+      }
+      false => None,
+    };
+
+    #[allow(unused_variables)]
+    let handle = notification.show().map_err(|e| e.to_string())?;
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Some(previous) = previous {
+      *self.replaced.lock().map_err(|e| e.to_string())? = Some(handle.id());
+      // the thread waiting on the notification that was replaced still answers for it
+      if previous == Some(handle.id()) {
+        return Ok(Delivery::Sent);
+      }
+    }
+
+    // the daemon answers through D-Bus signals, so wait for them without holding up delivery
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if !notice.actions.is_empty() {
+      let sender = action::sender();
+      std::thread::spawn(move || {
+        handle.wait_for_action(|id| {
+          if let Some(action) = Action::from_id(id) {
+            let _ = sender.send(action);
+          }
+        })
+      });
+    }
+
+    Ok(Delivery::Sent)
+  }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+  use std::{collections::HashMap, thread, time::Duration};
+
+  use zbus::{blocking::connection, interface, object_server::SignalContext, zvariant::OwnedValue};
+
+  use super::*;
+
+  /// Stand-in notification daemon that presses `press` on every notification it receives.
+  struct StandIn {
+    press: &'static str,
+  }
+
+  #[interface(name = "org.freedesktop.Notifications")]
+  impl StandIn {
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+      &self,
+      #[zbus(signal_context)] ctxt: SignalContext<'_>,
+      _app_name: &str,
+      _replaces_id: u32,
+      _app_icon: &str,
+      _summary: &str,
+      _body: &str,
+      actions: Vec<&str>,
+      _hints: HashMap<&str, OwnedValue>,
+      _expire_timeout: i32,
+    ) -> u32 {
+      const ID: u32 = 1;
+
+      assert!(actions.contains(&self.press));
+
+      let ctxt = ctxt.to_owned();
+      let press = self.press;
+      // give the client time to subscribe to the signal
+      thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        zbus::block_on(Self::action_invoked(&ctxt, ID, press)).unwrap();
+      });
+
+      ID
+    }
+
+    async fn get_capabilities(&self) -> Vec<&str> {
+      vec!["actions", "body"]
+    }
+
+    async fn get_server_information(&self) -> (&str, &str, &str, &str) {
+      ("stand-in", "timer", "0.1.0", "1.2")
+    }
+
+    async fn close_notification(&self, _id: u32) {}
+
+    #[zbus(signal)]
+    async fn action_invoked(ctxt: &SignalContext<'_>, id: u32, action_key: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn notification_closed(ctxt: &SignalContext<'_>, id: u32, reason: u32) -> zbus::Result<()>;
+  }
+
+  /// Talks to the stand-in over a private session bus, so it is left out of a plain `cargo test`.
+  /// Run it with `dbus-run-session -- cargo test action_routed_back -- --ignored`.
+  #[test]
+  #[ignore = "needs a session bus, run with `dbus-run-session -- cargo test -- --ignored`"]
+  fn action_routed_back() {
+    let _daemon = connection::Builder::session()
+      .unwrap()
+      .name("org.freedesktop.Notifications")
+      .unwrap()
+      .serve_at("/org/freedesktop/Notifications", StandIn { press: "skip" })
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let desktop = Desktop::new(NotificationLike::default());
+    let notice = Notice {
+      summary: "work finished".to_owned(),
+      actions: vec![(Action::Snooze, "Snooze".to_owned()), (Action::Skip, "Skip break".to_owned())],
+      ..Default::default()
+    };
+    assert_eq!(desktop.notify(&notice), Ok(Delivery::Sent));
+
+    let action = action::receiver()
+      .lock()
+      .unwrap()
+      .recv_timeout(Duration::from_secs(5))
+      .unwrap();
+    assert_eq!(action, Action::Skip);
+  }
+}
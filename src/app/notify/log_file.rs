@@ -0,0 +1,22 @@
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use chrono::Local;
+
+use super::*;
+
+pub struct LogFile {
+  pub path: PathBuf,
+}
+
+impl Notifier for LogFile {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String> {
+    let mut file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&self.path)
+      .map_err(|e| format!("{}: {e}", self.path.display()))?;
+
+    writeln!(file, "{} {notice}", Local::now().format("%Y-%m-%d %H:%M:%S")).map_err(|e| e.to_string())?;
+    Ok(Delivery::Sent)
+  }
+}
@@ -0,0 +1,19 @@
+use super::*;
+
+pub struct Stdout;
+
+impl Notifier for Stdout {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String> {
+    println!("{notice}");
+    Ok(Delivery::Sent)
+  }
+}
+
+pub struct Stderr;
+
+impl Notifier for Stderr {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String> {
+    eprintln!("{notice}");
+    Ok(Delivery::Sent)
+  }
+}
@@ -0,0 +1,208 @@
+use std::{
+  path::Path,
+  process::{Child, Command},
+  sync::{
+    mpsc::{self, RecvTimeoutError},
+    Mutex, OnceLock,
+  },
+  thread,
+  time::Duration,
+};
+
+use super::*;
+use crate::{app::config::SpeechMode, util::process};
+
+/// Speaks notices through a local TTS command such as `espeak-ng` or `spd-say`.
+///
+/// Announcements run on a worker thread so a long sentence does not hold up delivery.
+/// Every notifier hands them to the same worker, so two of them never talk over each other.
+pub struct Speech {
+  command: Vec<String>,
+  mode: SpeechMode,
+}
+
+/// An announcement along with the settings of the notifier that sent it.
+struct Job {
+  command: Vec<String>,
+  mode: SpeechMode,
+  text: String,
+}
+
+enum Work {
+  Speak(Job),
+  // answered once everything sent before it has been spoken
+  #[cfg(test)]
+  Flush(mpsc::Sender<()>),
+}
+
+// how often a finished announcement is reaped while the worker is idle
+const REAP: Duration = Duration::from_millis(200);
+
+impl Speech {
+  pub fn new(command: Vec<String>, mode: SpeechMode) -> Self {
+    Self { command, mode }
+  }
+}
+
+fn worker() -> &'static Mutex<mpsc::Sender<Work>> {
+  static WORKER: OnceLock<Mutex<mpsc::Sender<Work>>> = OnceLock::new();
+
+  WORKER.get_or_init(|| {
+    let (tx, rx) = mpsc::channel::<Work>();
+
+    thread::spawn(move || {
+      let mut current: Option<(Child, Vec<String>)> = None;
+
+      loop {
+        let work = match &mut current {
+          // a finished announcement is waited on right away, so it does not linger as a zombie
+          Some((child, _)) => match rx.recv_timeout(REAP) {
+            Ok(v) => v,
+            Err(RecvTimeoutError::Timeout) => {
+              if !matches!(child.try_wait(), Ok(None)) {
+                current = None;
+              }
+              continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+          },
+          None => match rx.recv() {
+            Ok(v) => v,
+            Err(_) => break,
+          },
+        };
+
+        match work {
+          Work::Speak(job) => {
+            if let Some((mut child, command)) = current.take() {
+              match job.mode {
+                SpeechMode::Queue => {
+                  let _ = child.wait();
+                }
+                SpeechMode::Interrupt => stop(&mut child, &command),
+              }
+            }
+
+            match speak(&job.command, &job.text) {
+              Ok(child) => current = Some((child, job.command)),
+              Err(e) => eprintln!("failed to speak: {e}"),
+            }
+          }
+          #[cfg(test)]
+          Work::Flush(done) => {
+            if let Some((mut child, _)) = current.take() {
+              let _ = child.wait();
+            }
+            let _ = done.send(());
+          }
+        }
+      }
+
+      if let Some((mut child, _)) = current {
+        let _ = child.wait();
+      }
+    });
+
+    Mutex::new(tx)
+  })
+}
+
+fn speak(command: &[String], text: &str) -> Result<Child, String> {
+  let (program, args) = command.split_first().ok_or("no speech command configured")?;
+
+  let mut speech = Command::new(program);
+  speech.args(args);
+  // spd-say hands the text to speech-dispatcher and returns right away unless told to wait
+  if spd_say(program) && !args.iter().any(|v| v == "--wait" || v == "-w") {
+    speech.arg("--wait");
+  }
+
+  process::new_group(&mut speech)
+    .arg(text)
+    .spawn()
+    .map_err(|e| format!("{program}: {e}"))
+}
+
+/// Silences `child` and whatever it started.
+fn stop(child: &mut Child, command: &[String]) {
+  process::kill_group(child.id());
+  let _ = child.wait();
+
+  // speech-dispatcher goes on talking after its client is gone
+  if let Some(program) = command.first().filter(|v| spd_say(v)) {
+    let _ = Command::new(program).arg("-C").status();
+  }
+}
+
+fn spd_say(program: &str) -> bool {
+  Path::new(program).file_stem().is_some_and(|v| v == "spd-say")
+}
+
+impl Notifier for Speech {
+  fn notify(&self, notice: &Notice) -> Result<Delivery, String> {
+    let text = match notice.body.is_empty() {
+      true => notice.summary.clone(),
+      false => format!("{}. {}", notice.summary, notice.body),
+    };
+
+    let job = Job {
+      command: self.command.clone(),
+      mode: self.mode,
+      text,
+    };
+    let tx = worker().lock().map_err(|e| e.to_string())?;
+    tx.send(Work::Speak(job)).map_err(|_| "speech worker stopped".to_owned())?;
+    Ok(Delivery::Sent)
+  }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+  use crate::util::test::temp_path;
+
+  // waits until the worker spoke everything sent so far
+  fn flush() {
+    let (tx, rx) = mpsc::channel();
+    worker().lock().unwrap().send(Work::Flush(tx)).unwrap();
+    rx.recv().unwrap();
+  }
+
+  // the text is written from a background job, which is only stopped along with the whole group
+  fn speak_to(file: &std::path::Path, delay: &str) -> Vec<String> {
+    let script = format!("(sleep {delay}; echo \"$0\" >> {}) & wait", file.display());
+    vec!["sh".to_owned(), "-c".to_owned(), script]
+  }
+
+  fn notice(summary: &str) -> Notice {
+    Notice {
+      summary: summary.to_owned(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn queue_and_interrupt() {
+    let queued = temp_path("speech-queue");
+    let interrupted = temp_path("speech-interrupt");
+
+    let queue = Speech::new(speak_to(&queued, "0.1"), SpeechMode::Queue);
+    queue.notify(&notice("first")).unwrap();
+    queue.notify(&notice("second")).unwrap();
+    flush();
+    assert_eq!(std::fs::read_to_string(&queued).unwrap(), "first\nsecond\n");
+
+    // a notifier made after a config reload goes through the same worker
+    let interrupt = Speech::new(speak_to(&interrupted, "0.5"), SpeechMode::Interrupt);
+    interrupt.notify(&notice("first")).unwrap();
+    Speech::new(speak_to(&interrupted, "0.5"), SpeechMode::Interrupt)
+      .notify(&notice("second"))
+      .unwrap();
+    flush();
+
+    assert_eq!(std::fs::read_to_string(&interrupted).unwrap(), "second\n");
+
+    std::fs::remove_file(&queued).unwrap();
+    std::fs::remove_file(&interrupted).unwrap();
+  }
+}
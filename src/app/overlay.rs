@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use chrono::Local;
+use iced::{
+  window::{self, Level, Mode},
+  Task,
+};
+
+use super::{timer::ticker::Phase, App, Message};
+
+/// Opens the overlay while a break is running and closes it once the break is over.
+pub(crate) fn sync(app: &mut App) -> Task<Message> {
+  let on_break = app.timer.enable && app.timer.phase() == Phase::Break && app.resume.is_none();
+
+  if !on_break {
+    app.overlay_dismissed = false;
+  }
+  if !on_break || app.overlay_postponed.is_some_and(|v| Local::now().naive_local() >= v) {
+    app.overlay_postponed = None;
+  }
+
+  let show = on_break && app.config.overlay.is_some() && !app.overlay_dismissed && app.overlay_postponed.is_none();
+
+  match (show, app.overlay) {
+    (true, None) => open(app),
+    (false, Some(id)) => {
+      app.overlay = None;
+      window::close(id)
+    }
+    _ => Task::none(),
+  }
+}
+
+fn open(app: &mut App) -> Task<Message> {
+  let settings = window::Settings {
+    decorations: false,
+    transparent: true,
+    level: Level::AlwaysOnTop,
+    ..Default::default()
+  };
+
+  let (id, open) = window::open(settings);
+  app.overlay = Some(id);
+
+  open
+    .discard()
+    .chain(window::change_mode(id, Mode::Fullscreen))
+    .chain(window::gain_focus(id))
+}
+
+/// Postpones left today.
+pub(crate) fn postpones_left(app: &App) -> u32 {
+  let Some(config) = &app.config.overlay else {
+    return 0;
+  };
+
+  let used = match app.state.postponed {
+    Some((date, count)) if date == Local::now().date_naive() => count,
+    _ => 0,
+  };
+  config.max_postpones.saturating_sub(used)
+}
+
+/// Hides the overlay for `duration` and makes the break that much longer, so none of it is lost.
+pub(crate) fn postpone(app: &mut App, duration: Duration) {
+  app.overlay_postponed = Some(Local::now().naive_local() + duration);
+  app.timer.extend(duration);
+
+  let today = Local::now().date_naive();
+  let count = match app.state.postponed {
+    Some((date, count)) if date == today => count,
+    _ => 0,
+  };
+  app.state.postponed = Some((today, count + 1));
+}
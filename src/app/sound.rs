@@ -0,0 +1,138 @@
+pub mod ambient;
+pub mod output;
+mod wav;
+
+use std::{
+  fs::File,
+  io::{BufReader, Cursor},
+  path::Path,
+  sync::Arc,
+  thread,
+  time::Duration,
+};
+
+use iced::Task;
+use output::AudioOutput;
+use rodio::{Decoder, Source};
+
+use super::{
+  config::{Output, SoundConfig},
+  timer::{ticker::Phase, TimerEvent},
+  App, Message,
+};
+
+pub type Clip = Box<dyn Source<Item = f32> + Send>;
+
+const CHIME: &[u8] = include_bytes!("../../assets/sounds/chime.wav");
+// longest clip written by the file output
+const MAX_RENDER: Duration = Duration::from_secs(60);
+
+pub(crate) fn output(config: &SoundConfig) -> Arc<dyn AudioOutput> {
+  match &config.output {
+    Output::Default => Arc::new(output::Speaker::default()),
+    Output::Null => Arc::new(output::Null::default()),
+    Output::File(path) => Arc::new(output::WavFile {
+      path: path.clone(),
+      max: MAX_RENDER,
+    }),
+  }
+}
+
+/// Plays the sound configured for `event`, if any, decoding it off the UI thread.
+pub(crate) fn play(app: &App, event: TimerEvent) -> Task<Message> {
+  let Some(sound) = app.config.sound.get(event).cloned() else {
+    return Task::none();
+  };
+  let output = app.audio.clone();
+
+  Task::perform(
+    async move {
+      tokio::task::spawn_blocking(move || {
+        load(sound.file.as_deref()).and_then(|clip| output.play(Box::new(clip.amplify(sound.volume))))
+      })
+      .await
+      .map_err(|e| e.to_string())?
+      .map_err(|e| format!("failed to play sound: {e}"))
+    },
+    Message::Played,
+  )
+}
+
+/// Runs the ambient sound of the current ticker during work phases and fades it out otherwise.
+///
+/// Between two work phases, as with the Normal ticker, the sound keeps playing.
+pub(crate) fn sync_ambient(app: &mut App) {
+  let config = app
+    .config
+    .ambient
+    .get(app.timer.ticker.name)
+    .filter(|_| app.timer.enable && app.timer.phase() == Phase::Work && app.resume.is_none());
+
+  match (config, &app.ambient) {
+    (Some(config), Some(ambient)) => ambient.set_volume(config.volume),
+    (Some(config), None) => {
+      let (generator, ambient) = ambient::Generator::new(config.noise, config.ticking, config.volume);
+      app.ambient = Some(ambient);
+
+      // the file output renders the clip before it returns
+      let output = app.audio.clone();
+      thread::spawn(move || {
+        if let Err(e) = output.play(Box::new(generator)) {
+          eprintln!("failed to play ambient sound: {e}");
+        }
+      });
+    }
+    (None, Some(_)) => {
+      if let Some(ambient) = app.ambient.take() {
+        ambient.fade_out();
+      }
+    }
+    (None, None) => (),
+  }
+}
+
+/// Decodes a WAV or OGG file, or the bundled chime without one.
+pub fn load(file: Option<&Path>) -> Result<Clip, String> {
+  Ok(match file {
+    Some(path) => {
+      let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+      let decoder = Decoder::new(BufReader::new(file)).map_err(|e| format!("{}: {e}", path.display()))?;
+      Box::new(decoder.convert_samples())
+    }
+    None => Box::new(Decoder::new(Cursor::new(CHIME)).map_err(|e| e.to_string())?.convert_samples()),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::Ordering;
+
+  use super::*;
+  use crate::util::test::temp_path;
+
+  #[test]
+  fn render_chime() {
+    let path = temp_path("chime.wav");
+    let output = output::WavFile {
+      path: path.clone(),
+      max: MAX_RENDER,
+    };
+
+    output.play(load(None).unwrap()).unwrap();
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(&bytes[..4], b"RIFF");
+    assert_eq!(&bytes[8..12], b"WAVE");
+    // the chime is 0.6s of 44.1kHz mono
+    assert_eq!(bytes.len(), 44 + 44100 * 6 / 10 * 2);
+
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn null_output() {
+    let output = output::Null::default();
+    output.play(Box::new(load(None).unwrap().amplify(0.5))).unwrap();
+    assert_eq!(output.played.load(Ordering::Relaxed), 1);
+  }
+}
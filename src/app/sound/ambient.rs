@@ -0,0 +1,187 @@
+use std::{
+  sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+
+const SAMPLE_RATE: u32 = 44100;
+const FADE: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Noise {
+  White,
+  Pink,
+  Brown,
+}
+
+/// Shared between the generator and the app, so the app can fade it out.
+#[derive(Debug, Default)]
+struct Control {
+  // target gain as `f32` bits
+  target: AtomicU32,
+  // end the source once the gain reached zero
+  stopping: AtomicBool,
+}
+
+/// Handle to a running [`Generator`].
+#[derive(Debug)]
+pub struct Ambient {
+  control: Arc<Control>,
+}
+
+impl Ambient {
+  pub fn set_volume(&self, volume: f32) {
+    self.control.target.store(volume.to_bits(), Ordering::Relaxed);
+  }
+
+  pub fn fade_out(&self) {
+    self.set_volume(0.0);
+    self.control.stopping.store(true, Ordering::Relaxed);
+  }
+}
+
+/// Endless noise and/or clock ticking, faded in and out smoothly.
+pub struct Generator {
+  noise: Option<Noise>,
+  ticking: bool,
+  control: Arc<Control>,
+  gain: f32,
+  rng: u64,
+  pink: [f32; 7],
+  brown: f32,
+  sample: u64,
+}
+
+impl Generator {
+  pub fn new(noise: Option<Noise>, ticking: bool, volume: f32) -> (Self, Ambient) {
+    let control = Arc::new(Control::default());
+    let ambient = Ambient {
+      control: control.clone(),
+    };
+    ambient.set_volume(volume);
+
+    let generator = Self {
+      noise,
+      ticking,
+      control,
+      gain: 0.0,
+      rng: 0x2545_f491_4f6c_dd1d,
+      pink: [0.0; 7],
+      brown: 0.0,
+      sample: 0,
+    };
+
+    (generator, ambient)
+  }
+
+  // xorshift, uniform in -1..1
+  fn white(&mut self) -> f32 {
+    self.rng ^= self.rng << 13;
+    self.rng ^= self.rng >> 7;
+    self.rng ^= self.rng << 17;
+    (self.rng >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+  }
+
+  // Paul Kellet's refined filter
+  fn pink(&mut self) -> f32 {
+    let white = self.white();
+    let b = &mut self.pink;
+    b[0] = 0.99886 * b[0] + white * 0.0555179;
+    b[1] = 0.99332 * b[1] + white * 0.0750759;
+    b[2] = 0.96900 * b[2] + white * 0.153852;
+    b[3] = 0.86650 * b[3] + white * 0.3104856;
+    b[4] = 0.55000 * b[4] + white * 0.5329522;
+    b[5] = -0.7616 * b[5] - white * 0.0168980;
+    let pink = b.iter().sum::<f32>() + white * 0.5362;
+    b[6] = white * 0.115926;
+    pink * 0.11
+  }
+
+  fn brown(&mut self) -> f32 {
+    let white = self.white();
+    self.brown = (self.brown + 0.02 * white) / 1.02;
+    self.brown * 3.5
+  }
+
+  // a short decaying click every second
+  fn tick(&self) -> f32 {
+    let t = (self.sample % SAMPLE_RATE as u64) as f32 / SAMPLE_RATE as f32;
+    if t > 0.02 {
+      return 0.0;
+    }
+    (t * 2000.0 * std::f32::consts::TAU).sin() * (1.0 - t / 0.02)
+  }
+}
+
+impl Iterator for Generator {
+  type Item = f32;
+
+  fn next(&mut self) -> Option<f32> {
+    let target = f32::from_bits(self.control.target.load(Ordering::Relaxed));
+    let step = 1.0 / (FADE.as_secs_f32() * SAMPLE_RATE as f32);
+    self.gain = match self.gain < target {
+      true => (self.gain + step).min(target),
+      false => (self.gain - step).max(target),
+    };
+
+    if self.gain <= 0.0 && self.control.stopping.load(Ordering::Relaxed) {
+      return None;
+    }
+
+    let noise = match self.noise {
+      Some(Noise::White) => self.white() * 0.3,
+      Some(Noise::Pink) => self.pink(),
+      Some(Noise::Brown) => self.brown(),
+      None => 0.0,
+    };
+    let tick = if self.ticking { self.tick() * 0.5 } else { 0.0 };
+    self.sample += 1;
+
+    Some(((noise + tick) * self.gain).clamp(-1.0, 1.0))
+  }
+}
+
+impl Source for Generator {
+  fn current_frame_len(&self) -> Option<usize> {
+    None
+  }
+
+  fn channels(&self) -> u16 {
+    1
+  }
+
+  fn sample_rate(&self) -> u32 {
+    SAMPLE_RATE
+  }
+
+  fn total_duration(&self) -> Option<Duration> {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fades_in_and_out() {
+    for noise in [Some(Noise::White), Some(Noise::Pink), Some(Noise::Brown), None] {
+      let (mut generator, ambient) = Generator::new(noise, true, 0.8);
+
+      let samples: Vec<f32> = generator.by_ref().take(SAMPLE_RATE as usize * 3).collect();
+      assert!(samples.iter().all(|v| (-1.0..=1.0).contains(v)));
+      assert!(samples.iter().any(|v| *v != 0.0));
+
+      ambient.fade_out();
+      let rest = generator.by_ref().take(SAMPLE_RATE as usize * 3).count();
+      assert!(rest < SAMPLE_RATE as usize * 2);
+      assert!(generator.next().is_none());
+    }
+  }
+}
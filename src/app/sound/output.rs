@@ -0,0 +1,103 @@
+use std::{
+  fs::File,
+  io::BufWriter,
+  path::PathBuf,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc, Mutex,
+  },
+  thread,
+  time::Duration,
+};
+
+use rodio::{OutputStream, Source};
+
+use super::{wav, Clip};
+
+/// Where sounds end up, so tests can run on machines without a sound card.
+pub trait AudioOutput: Send + Sync {
+  fn play(&self, clip: Clip) -> Result<(), String>;
+}
+
+/// The default output device, driven from its own thread since the stream can not be moved.
+///
+/// The device is opened by the first clip, so the app does not hold it while no sound is configured.
+#[derive(Default)]
+pub struct Speaker {
+  tx: Mutex<Option<mpsc::Sender<Clip>>>,
+}
+
+impl Speaker {
+  fn open() -> Result<mpsc::Sender<Clip>, String> {
+    let (tx, rx) = mpsc::channel::<Clip>();
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+      let (_stream, handle) = match OutputStream::try_default() {
+        Ok(v) => {
+          let _ = ready_tx.send(Ok(()));
+          v
+        }
+        Err(e) => {
+          let _ = ready_tx.send(Err(e.to_string()));
+          return;
+        }
+      };
+
+      for clip in rx {
+        if let Err(e) = handle.play_raw(clip) {
+          eprintln!("failed to play sound: {e}");
+        }
+      }
+    });
+
+    ready_rx.recv().map_err(|e| e.to_string())??;
+
+    Ok(tx)
+  }
+}
+
+impl AudioOutput for Speaker {
+  fn play(&self, clip: Clip) -> Result<(), String> {
+    let mut tx = self.tx.lock().map_err(|e| e.to_string())?;
+    let sender = match &*tx {
+      Some(v) => v.clone(),
+      None => {
+        let v = Self::open().map_err(|e| format!("no audio output: {e}"))?;
+        *tx = Some(v.clone());
+        v
+      }
+    };
+    sender.send(clip).map_err(|_| "audio thread stopped".to_owned())
+  }
+}
+
+/// Drops every clip.
+#[derive(Default)]
+pub struct Null {
+  pub played: AtomicUsize,
+}
+
+impl AudioOutput for Null {
+  fn play(&self, _clip: Clip) -> Result<(), String> {
+    self.played.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+  }
+}
+
+/// Renders each clip to a WAV file, cutting endless sources off at `max`.
+pub struct WavFile {
+  pub path: PathBuf,
+  pub max: Duration,
+}
+
+impl AudioOutput for WavFile {
+  fn play(&self, clip: Clip) -> Result<(), String> {
+    let channels = clip.channels();
+    let sample_rate = clip.sample_rate();
+    let max = (self.max.as_secs_f32() * sample_rate as f32) as usize * channels as usize;
+
+    let file = File::create(&self.path).map_err(|e| format!("{}: {e}", self.path.display()))?;
+    wav::write(BufWriter::new(file), channels, sample_rate, clip.take(max)).map_err(|e| e.to_string())
+  }
+}
@@ -0,0 +1,38 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Writes 16 bit PCM samples, filling in the sizes once the length is known.
+pub fn write<W: Write + Seek>(
+  mut w: W,
+  channels: u16,
+  sample_rate: u32,
+  samples: impl Iterator<Item = f32>,
+) -> io::Result<()> {
+  const BITS: u16 = 16;
+  let block_align = channels * BITS / 8;
+
+  w.write_all(b"RIFF")?;
+  w.write_all(&0u32.to_le_bytes())?;
+  w.write_all(b"WAVEfmt ")?;
+  w.write_all(&16u32.to_le_bytes())?;
+  w.write_all(&1u16.to_le_bytes())?;
+  w.write_all(&channels.to_le_bytes())?;
+  w.write_all(&sample_rate.to_le_bytes())?;
+  w.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+  w.write_all(&block_align.to_le_bytes())?;
+  w.write_all(&BITS.to_le_bytes())?;
+  w.write_all(b"data")?;
+  w.write_all(&0u32.to_le_bytes())?;
+
+  let mut len = 0u32;
+  for sample in samples {
+    let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+    w.write_all(&sample.to_le_bytes())?;
+    len += 2;
+  }
+
+  w.seek(SeekFrom::Start(4))?;
+  w.write_all(&(36 + len).to_le_bytes())?;
+  w.seek(SeekFrom::Start(40))?;
+  w.write_all(&len.to_le_bytes())?;
+  w.flush()
+}
@@ -0,0 +1,160 @@
+use std::{path::PathBuf, sync::mpsc, thread};
+
+use chrono::NaiveDate;
+use configu::{Config, Configurable};
+use serde::{Deserialize, Serialize};
+
+use super::{
+  config,
+  counter::Counts,
+  journal::{Journal, JournalEvent},
+  timer::TimerState,
+  App,
+};
+
+const STATE_FILE: &str = "timer.state.toml";
+
+/// Runtime state kept next to `timer.toml` so it survives restarts.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AppState {
+  pub timer: Option<TimerState>,
+  // break overlay postpones used on that day
+  pub postponed: Option<(NaiveDate, u32)>,
+  // habit counts per day
+  pub counts: Counts,
+  // last day the counter summary was sent for
+  pub summarized: Option<NaiveDate>,
+}
+
+enum Write {
+  Entry(JournalEvent, TimerState),
+  // the journal is cut off once the snapshot is on disk
+  Snapshot(AppState),
+  Flush(mpsc::Sender<()>),
+}
+
+/// Writes journal entries and state snapshots in order on a background thread.
+pub struct Writer {
+  tx: mpsc::Sender<Write>,
+}
+
+impl Writer {
+  pub fn new(path: PathBuf, mut journal: Option<Journal>) -> Self {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+      for write in rx {
+        match write {
+          Write::Entry(event, state) => {
+            if let Some(journal) = &mut journal {
+              if let Err(e) = journal.append(event, state) {
+                eprintln!("failed to write journal: {e}");
+              }
+            }
+          }
+          Write::Snapshot(state) => {
+            let mut config = Config::<AppState>::open(Some(path.clone()));
+            *config = state;
+
+            if let Err(e) = config.save() {
+              eprintln!("failed to save state: {e}");
+              continue;
+            }
+            if let Some(journal) = &mut journal {
+              if let Err(e) = journal.clear() {
+                eprintln!("failed to clear journal: {e}");
+              }
+            }
+          }
+          Write::Flush(done) => {
+            let _ = done.send(());
+          }
+        }
+      }
+    });
+
+    Self { tx }
+  }
+
+  fn send(&self, write: Write) {
+    if self.tx.send(write).is_err() {
+      eprintln!("state writer stopped");
+    }
+  }
+
+  /// Blocks until everything sent so far is on disk.
+  pub fn flush(&self) {
+    let (tx, rx) = mpsc::channel();
+    self.send(Write::Flush(tx));
+    let _ = rx.recv();
+  }
+}
+
+pub(crate) fn open() -> Config<AppState> {
+  config::open::<AppState>(config::path(STATE_FILE)).unwrap_or_else(|e| {
+    eprintln!("failed to load state: {e}");
+    Config::open(Some(config::path(STATE_FILE)))
+  })
+}
+
+pub(crate) fn writer(journal: Option<Journal>) -> Writer {
+  Writer::new(config::path(STATE_FILE), journal)
+}
+
+/// Journals a timer transition and saves the state file.
+pub(crate) fn record(app: &mut App, event: JournalEvent) {
+  if app.resume.is_some() {
+    return;
+  }
+
+  app.writer.send(Write::Entry(event, app.timer.snapshot()));
+  save(app);
+}
+
+/// Saves the state file on a clean shutdown and waits for it to be written.
+pub(crate) fn close(app: &mut App) {
+  save(app);
+  app.writer.flush();
+}
+
+/// Saves the state file, counters and postpones included even while the resume prompt is open.
+pub(crate) fn save(app: &mut App) {
+  // keep the previous timer until the user decided whether to resume it
+  app.state.timer = match &app.resume {
+    Some(saved) => Some(saved.clone()),
+    None => Some(app.timer.snapshot()),
+  };
+  app.writer.send(Write::Snapshot(AppState::clone(&app.state)));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{app::timer::Timer, util::test::temp_path};
+
+  #[test]
+  fn snapshot_truncates_journal() {
+    let journal_path = temp_path("state-journal");
+    let state_path = temp_path("state.toml");
+
+    let (journal, _) = Journal::recover(&journal_path).unwrap();
+    let writer = Writer::new(state_path.clone(), Some(journal));
+
+    let timer = Timer::default();
+    writer.send(Write::Entry(JournalEvent::Start, timer.snapshot()));
+    writer.flush();
+    assert!(std::fs::metadata(&journal_path).unwrap().len() > 0);
+
+    writer.send(Write::Snapshot(AppState {
+      timer: Some(timer.snapshot()),
+      ..Default::default()
+    }));
+    writer.flush();
+    assert_eq!(std::fs::metadata(&journal_path).unwrap().len(), 0);
+    assert!(state_path.is_file());
+
+    std::fs::remove_file(&journal_path).unwrap();
+    std::fs::remove_file(&state_path).unwrap();
+  }
+}
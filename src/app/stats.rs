@@ -0,0 +1,305 @@
+use std::{collections::BTreeSet, fmt, time::Duration};
+
+use chrono::{Days, Local, NaiveDate, Timelike};
+
+use super::{
+  history::{Outcome, Query, Session},
+  timer::ticker::Phase,
+  App,
+};
+
+// how far back streaks are looked for
+const STREAK_DAYS: u64 = 365;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Range {
+  #[default]
+  Day,
+  Week,
+  Month,
+}
+
+impl Range {
+  pub const ALL: &[Range] = &[Range::Day, Range::Week, Range::Month];
+
+  /// Number of days shown, ending today.
+  fn days(&self) -> u64 {
+    match self {
+      Range::Day => 1,
+      Range::Week => 7,
+      Range::Month => 30,
+    }
+  }
+}
+
+impl fmt::Display for Range {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Range::Day => write!(f, "day"),
+      Range::Week => write!(f, "week"),
+      Range::Month => write!(f, "month"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Filter {
+  #[default]
+  All,
+  Task(String),
+  Tag(String),
+}
+
+impl fmt::Display for Filter {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Filter::All => write!(f, "all"),
+      Filter::Task(v) => write!(f, "{v}"),
+      Filter::Tag(v) => write!(f, "#{v}"),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum StatsMessage {
+  Range(Range),
+  Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+  pub label: String,
+  pub minutes: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+  // focus minutes per hour of the day or per day
+  pub bars: Vec<Bar>,
+  pub focus: Duration,
+  pub pomodoros: u32,
+  // share of breaks that were taken in full
+  pub adherence: Option<f32>,
+  pub streak: u32,
+  pub best_streak: u32,
+}
+
+/// State of the statistics page.
+#[derive(Debug, Clone, Default)]
+pub struct StatsPage {
+  pub range: Range,
+  pub filter: Filter,
+  // every task and tag in the history
+  pub filters: Vec<Filter>,
+  pub stats: Stats,
+}
+
+impl StatsPage {
+  /// Sessions of the selected range and filter.
+  pub fn query(&self, today: NaiveDate) -> Query {
+    self.query_days(today, self.range.days())
+  }
+
+  fn query_days(&self, today: NaiveDate, days: u64) -> Query {
+    let (task, tag) = match &self.filter {
+      Filter::All => (None, None),
+      Filter::Task(v) => (Some(v.clone()), None),
+      Filter::Tag(v) => (None, Some(v.clone())),
+    };
+
+    Query {
+      task,
+      tag,
+      ..Query::days(today - Days::new(days - 1), today)
+    }
+  }
+}
+
+pub(crate) fn update(app: &mut App, message: StatsMessage) {
+  match message {
+    StatsMessage::Range(range) => app.stats.range = range,
+    StatsMessage::Filter(filter) => app.stats.filter = filter,
+  }
+  refresh(app);
+}
+
+/// Reads the history again for the selected range and filter.
+pub(crate) fn refresh(app: &mut App) {
+  let Some(history) = &app.history else {
+    return;
+  };
+
+  let today = Local::now().date_naive();
+  let page = &mut app.stats;
+
+  let result = history.sessions(&page.query(today)).and_then(|sessions| {
+    let streak = history.sessions(&Query {
+      phase: Some(Phase::Work),
+      ..page.query_days(today, STREAK_DAYS)
+    })?;
+    Ok((sessions, streak))
+  });
+
+  let filters = history.tasks().and_then(|tasks| Ok((tasks, history.tags()?)));
+
+  match result {
+    Ok((sessions, streak)) => {
+      page.stats = compute(&sessions, page.range, today);
+      (page.stats.streak, page.stats.best_streak) = streaks(&focus_days(&streak), today);
+    }
+    Err(e) => eprintln!("failed to read history: {e}"),
+  }
+
+  match filters {
+    Ok((tasks, tags)) => {
+      page.filters = std::iter::once(Filter::All)
+        .chain(tasks.into_iter().map(Filter::Task))
+        .chain(tags.into_iter().map(Filter::Tag))
+        .collect();
+    }
+    Err(e) => eprintln!("failed to read history: {e}"),
+  }
+}
+
+/// Totals of `sessions`, which all start within `range` ending `today`.
+pub fn compute(sessions: &[Session], range: Range, today: NaiveDate) -> Stats {
+  let mut bars: Vec<Bar> = match range {
+    Range::Day => (0..24)
+      .map(|hour| Bar {
+        label: hour.to_string(),
+        minutes: 0.0,
+      })
+      .collect(),
+    _ => (0..range.days())
+      .rev()
+      .map(|v| Bar {
+        label: (today - Days::new(v)).format(if range == Range::Week { "%a" } else { "%d" }).to_string(),
+        minutes: 0.0,
+      })
+      .collect(),
+  };
+
+  let mut stats = Stats::default();
+  let (mut breaks, mut taken) = (0, 0);
+
+  for session in sessions {
+    match session.phase {
+      Phase::Work => {
+        let index = match range {
+          Range::Day => session.start.hour() as usize,
+          _ => {
+            let days = (today - session.start.date_naive()).num_days();
+            (range.days() as i64 - 1 - days) as usize
+          }
+        };
+        if let Some(bar) = bars.get_mut(index) {
+          bar.minutes += session.actual.as_secs_f32() / 60.0;
+        }
+
+        stats.focus += session.actual;
+        if session.outcome == Outcome::Completed {
+          stats.pomodoros += 1;
+        }
+      }
+      Phase::Break => {
+        breaks += 1;
+        if session.outcome == Outcome::Completed {
+          taken += 1;
+        }
+      }
+    }
+  }
+
+  stats.bars = bars;
+  stats.adherence = (breaks > 0).then(|| taken as f32 / breaks as f32);
+  stats
+}
+
+/// Days with at least one completed work phase.
+fn focus_days(sessions: &[Session]) -> BTreeSet<NaiveDate> {
+  sessions
+    .iter()
+    .filter(|v| v.phase == Phase::Work && v.outcome == Outcome::Completed)
+    .map(|v| v.start.date_naive())
+    .collect()
+}
+
+/// Current and longest run of consecutive days, a streak is still alive until today is over.
+pub fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+  let (mut best, mut run, mut previous) = (0, 0, None::<NaiveDate>);
+  for day in days {
+    run = match previous {
+      Some(v) if v.succ_opt() == Some(*day) => run + 1,
+      _ => 1,
+    };
+    best = best.max(run);
+    previous = Some(*day);
+  }
+
+  let start = if days.contains(&today) { today } else { today - Days::new(1) };
+  let current = std::iter::successors(Some(start), NaiveDate::pred_opt)
+    .take_while(|v| days.contains(v))
+    .count();
+
+  (current as u32, best)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn session(start: chrono::DateTime<Local>, phase: Phase, minutes: u64, outcome: Outcome) -> Session {
+    let actual = Duration::from_secs(minutes * 60);
+    Session {
+      id: 0,
+      start,
+      end: start + actual,
+      ticker: "Pomodoro".to_owned(),
+      phase,
+      planned: actual,
+      actual,
+      outcome,
+      task: None,
+      tags: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn week_totals() {
+    let today = NaiveDate::from_ymd_opt(2024, 2, 14).unwrap();
+    let at = |days: u64, hour: u32| {
+      let date = today - Days::new(days);
+      Query::days(date, date).from.unwrap() + chrono::Duration::hours(hour as i64)
+    };
+
+    let sessions = [
+      session(at(6, 9), Phase::Work, 25, Outcome::Completed),
+      session(at(6, 10), Phase::Break, 5, Outcome::Completed),
+      session(at(0, 9), Phase::Work, 10, Outcome::Interrupted),
+      session(at(0, 10), Phase::Break, 1, Outcome::Skipped),
+    ];
+
+    let stats = compute(&sessions, Range::Week, today);
+    assert_eq!(stats.bars.len(), 7);
+    assert_eq!(stats.bars[0].minutes, 25.0);
+    assert_eq!(stats.bars[6].minutes, 10.0);
+    assert_eq!(stats.focus, Duration::from_secs(35 * 60));
+    assert_eq!(stats.pomodoros, 1);
+    assert_eq!(stats.adherence, Some(0.5));
+
+    let stats = compute(&sessions[2..], Range::Day, today);
+    assert_eq!(stats.bars[9].minutes, 10.0);
+  }
+
+  #[test]
+  fn streak_survives_until_today_is_over() {
+    let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    let days: BTreeSet<_> = [1, 2, 3, 5, 6, 7, 8, 9].into_iter().map(|v| today - Days::new(v)).collect();
+
+    assert_eq!(streaks(&days, today), (3, 5));
+
+    let mut days = days;
+    days.insert(today);
+    assert_eq!(streaks(&days, today), (4, 5));
+  }
+}
@@ -0,0 +1,325 @@
+mod import;
+
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use configu::{Config, Configurable};
+use serde::{Deserialize, Serialize};
+
+use super::{config, info, App, Message};
+
+const TASK_FILE: &str = "tasks.toml";
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+  pub id: u32,
+  pub title: String,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  // pomodoros
+  #[serde(default)]
+  pub estimate: u32,
+  // work phases completed while the task was active
+  #[serde(default)]
+  pub actual: u32,
+  #[serde(default)]
+  pub done: bool,
+  // file the task was imported from
+  #[serde(default)]
+  pub source: Option<Source>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+  pub path: PathBuf,
+  pub key: String,
+  #[serde(default)]
+  pub occurrence: usize,
+}
+
+/// Layout of `tasks.toml`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TaskList {
+  pub next_id: u32,
+  pub active: Option<u32>,
+  pub task: Vec<Task>,
+}
+
+impl TaskList {
+  pub fn add(&mut self, title: String, tags: Vec<String>, estimate: u32) -> u32 {
+    let id = self.next_id;
+    self.next_id += 1;
+    self.task.push(Task {
+      id,
+      title,
+      tags,
+      estimate,
+      actual: 0,
+      done: false,
+      source: None,
+    });
+    id
+  }
+
+  /// Adds the items of `path` that are new and updates the others, returns how many were added.
+  ///
+  /// Tasks whose line is gone are kept, but no longer tied to the file, returns how many as well.
+  pub fn merge(&mut self, path: &Path, items: Vec<import::Item>) -> (usize, usize) {
+    let mut detached = 0;
+    for task in &mut self.task {
+      let gone = task.source.as_ref().is_some_and(|source| {
+        source.path == path && !items.iter().any(|v| v.key == source.key && v.occurrence == source.occurrence)
+      });
+      if gone {
+        task.source = None;
+        detached += 1;
+      }
+    }
+
+    let mut added = 0;
+    for item in items {
+      let source = Source {
+        path: path.to_owned(),
+        key: item.key,
+        occurrence: item.occurrence,
+      };
+
+      match self.task.iter().position(|v| v.source.as_ref() == Some(&source)) {
+        Some(i) => {
+          let task = &mut self.task[i];
+          task.title = item.title;
+          task.tags = item.tags;
+          let id = task.id;
+          self.set_done(id, item.done);
+        }
+        None => {
+          let id = self.add(item.title, item.tags, 0);
+          self.set_done(id, item.done);
+          if let Some(task) = self.get_mut(id) {
+            task.source = Some(source);
+          }
+          added += 1;
+        }
+      }
+    }
+
+    (added, detached)
+  }
+
+  pub fn get(&self, id: u32) -> Option<&Task> {
+    self.task.iter().find(|v| v.id == id)
+  }
+
+  pub fn get_mut(&mut self, id: u32) -> Option<&mut Task> {
+    self.task.iter_mut().find(|v| v.id == id)
+  }
+
+  pub fn active(&self) -> Option<&Task> {
+    self.active.and_then(|id| self.get(id))
+  }
+
+  pub fn remove(&mut self, id: u32) {
+    self.task.retain(|v| v.id != id);
+    if self.active == Some(id) {
+      self.active = None;
+    }
+  }
+
+  /// Marks `id` done or open again, a task that is done can not stay active.
+  pub fn set_done(&mut self, id: u32, done: bool) {
+    if let Some(task) = self.get_mut(id) {
+      task.done = done;
+    }
+    if done && self.active == Some(id) {
+      self.active = None;
+    }
+  }
+
+  /// Counts a completed work phase for the active task.
+  pub fn complete_pomodoro(&mut self) -> Option<&Task> {
+    let task = self.get_mut(self.active?)?;
+    task.actual += 1;
+    Some(task)
+  }
+
+  /// Takes back the last pomodoro of the active task.
+  pub fn undo_pomodoro(&mut self) {
+    if let Some(task) = self.active.and_then(|id| self.get_mut(id)) {
+      task.actual = task.actual.saturating_sub(1);
+    }
+  }
+}
+
+/// Text of the new task form.
+#[derive(Debug, Clone, Default)]
+pub struct Form {
+  pub title: String,
+  // space separated, a leading `#` is dropped
+  pub tags: String,
+  pub estimate: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum TaskMessage {
+  Title(String),
+  Tags(String),
+  Estimate(String),
+  Add,
+  Activate(Option<u32>),
+  Done(u32, bool),
+  Remove(u32),
+  // a watched source was modified
+  Changed(PathBuf),
+}
+
+/// Opens `tasks.toml`, a file that can not be read is left alone and the list is not saved.
+pub(crate) fn open() -> (Config<TaskList>, Option<String>) {
+  match config::open::<TaskList>(config::path(TASK_FILE)) {
+    Ok(tasks) => (tasks, None),
+    Err(e) => (
+      Config::open(None),
+      Some(format!("failed to load tasks, changes will not be saved: {e}")),
+    ),
+  }
+}
+
+pub(crate) fn update(app: &mut App, message: TaskMessage) -> iced::Task<Message> {
+  match message {
+    TaskMessage::Title(v) => app.task_form.title = v,
+    TaskMessage::Tags(v) => app.task_form.tags = v,
+    TaskMessage::Estimate(v) => app.task_form.estimate = v.chars().filter(char::is_ascii_digit).collect(),
+    TaskMessage::Add => {
+      let form = std::mem::take(&mut app.task_form);
+      let title = form.title.trim();
+      if title.is_empty() {
+        app.task_form = form;
+        return info::send("a task needs a title");
+      }
+
+      let tags = form
+        .tags
+        .split_whitespace()
+        .map(|v| v.trim_start_matches('#').to_owned())
+        .filter(|v| !v.is_empty())
+        .collect();
+      app.tasks.add(title.to_owned(), tags, form.estimate.parse().unwrap_or_default());
+    }
+    TaskMessage::Activate(id) => app.tasks.active = id,
+    TaskMessage::Done(id, done) => {
+      app.tasks.set_done(id, done);
+
+      let source = app.tasks.get(id).and_then(|v| v.source.clone());
+      if let Some(source) = source {
+        return iced::Task::batch([save(app), write_back(source, done)]);
+      }
+    }
+    TaskMessage::Remove(id) => app.tasks.remove(id),
+    TaskMessage::Changed(path) => return import_file(app, &path),
+  }
+
+  save(app)
+}
+
+/// Marks the task in the file it came from, off the UI thread since the file may be on a slow disk.
+fn write_back(source: Source, done: bool) -> iced::Task<Message> {
+  let today = Local::now().date_naive();
+
+  iced::Task::perform(
+    async move {
+      tokio::task::spawn_blocking(move || {
+        import::write_back(&source.path, &source.key, source.occurrence, done, today)
+      })
+      .await
+      .map_err(|e| e.to_string())?
+    },
+    |result: Result<(), String>| result.err(),
+  )
+  .and_then(|e| info::send(format!("failed to update task file: {e}")))
+}
+
+/// Imports every configured task file.
+pub(crate) fn import_all(app: &mut App) -> iced::Task<Message> {
+  let paths: Vec<_> = app.config.task_sources.iter().map(|v| v.path.clone()).collect();
+  iced::Task::batch(paths.iter().map(|v| import_file(app, v)).collect::<Vec<_>>())
+}
+
+fn import_file(app: &mut App, path: &Path) -> iced::Task<Message> {
+  match import::read(path) {
+    Ok(items) => {
+      let (added, detached) = app.tasks.merge(path, items);
+      let mut tasks = vec![save(app)];
+      if added > 0 {
+        tasks.push(info::send(format!("imported {added} tasks from {}", path.display())));
+      }
+      if detached > 0 {
+        tasks.push(info::send(format!(
+          "{detached} tasks are no longer in {}, kept them as local tasks",
+          path.display()
+        )));
+      }
+      iced::Task::batch(tasks)
+    }
+    Err(e) => info::send(format!("failed to import tasks: {e}")),
+  }
+}
+
+pub(crate) fn save(app: &mut App) -> iced::Task<Message> {
+  if app.tasks.file_path.is_none() {
+    return iced::Task::none();
+  }
+
+  match app.tasks.save() {
+    Ok(()) => iced::Task::none(),
+    Err(e) => info::send(format!("failed to save tasks: {e}")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pomodoros_count_for_the_active_task() {
+    let mut tasks = TaskList::default();
+    let write = tasks.add("write".to_owned(), vec!["docs".to_owned()], 3);
+    let read = tasks.add("read".to_owned(), Vec::new(), 1);
+
+    assert!(tasks.complete_pomodoro().is_none());
+
+    tasks.active = Some(write);
+    tasks.complete_pomodoro();
+    tasks.complete_pomodoro();
+    assert_eq!(tasks.get(write).map(|v| v.actual), Some(2));
+    assert_eq!(tasks.get(read).map(|v| v.actual), Some(0));
+
+    tasks.set_done(write, true);
+    assert_eq!(tasks.active, None);
+
+    tasks.remove(read);
+    assert_eq!(tasks.task.len(), 1);
+  }
+
+  #[test]
+  fn merge_updates_imported_tasks() {
+    let mut tasks = TaskList::default();
+    let path = Path::new("todo.txt");
+
+    let items = import::parse(import::Format::TodoTxt, "(A) Call mom +family\nPay rent\n");
+    assert_eq!(tasks.merge(path, items), (2, 0));
+    tasks.active = Some(0);
+
+    let items = import::parse(import::Format::TodoTxt, "x 2024-02-14 Call mom +family pri:A\nPay rent\n");
+    assert_eq!(tasks.merge(path, items), (0, 0));
+    assert_eq!(tasks.task.len(), 2);
+    assert!(tasks.task[0].done);
+    assert_eq!(tasks.task[0].tags, vec!["pri:A", "family"]);
+    assert_eq!(tasks.active, None);
+
+    // the rent line is gone, the task stays without a source
+    let items = import::parse(import::Format::TodoTxt, "x 2024-02-14 Call mom +family pri:A\n");
+    assert_eq!(tasks.merge(path, items), (0, 1));
+    assert_eq!(tasks.task.len(), 2);
+    assert!(tasks.task[1].source.is_none());
+  }
+}
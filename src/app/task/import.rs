@@ -0,0 +1,343 @@
+use std::{collections::HashMap, fs, path::Path, sync::Mutex};
+
+use chrono::NaiveDate;
+
+/// Format of a task file, told apart by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+  TodoTxt,
+  Markdown,
+}
+
+impl Format {
+  pub fn from_path(path: &Path) -> Self {
+    match path.extension().and_then(|v| v.to_str()) {
+      Some("md" | "markdown") => Format::Markdown,
+      _ => Format::TodoTxt,
+    }
+  }
+}
+
+/// A task read from a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+  // finds the line again, stays the same when the task is completed
+  pub key: String,
+  // tells apart lines with the same key, counted from the top
+  pub occurrence: usize,
+  pub title: String,
+  pub tags: Vec<String>,
+  pub done: bool,
+}
+
+/// A todo.txt line, see <https://github.com/todotxt/todo.txt>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Line {
+  done: bool,
+  priority: Option<char>,
+  created: Option<String>,
+  // without `pri:` which only done tasks carry
+  description: String,
+}
+
+impl Line {
+  fn parse(line: &str) -> Option<Self> {
+    let mut rest = line.trim();
+    if rest.is_empty() {
+      return None;
+    }
+
+    let done = match rest.strip_prefix("x ") {
+      Some(v) => {
+        rest = v.trim_start();
+        // completion date
+        if let Some((_, v)) = date(rest) {
+          rest = v;
+        }
+        true
+      }
+      None => false,
+    };
+
+    let mut priority = None;
+    if let Some((p, v)) = priority_of(rest) {
+      priority = Some(p);
+      rest = v;
+    }
+
+    let mut created = None;
+    if let Some((d, v)) = date(rest) {
+      created = Some(d.to_owned());
+      rest = v;
+    }
+
+    let mut words = Vec::new();
+    for word in rest.split_whitespace() {
+      match word.strip_prefix("pri:").and_then(single_upper) {
+        Some(p) => priority = priority.or(Some(p)),
+        None => words.push(word),
+      }
+    }
+
+    Some(Self {
+      done,
+      priority,
+      created,
+      description: words.join(" "),
+    })
+  }
+
+  /// Done tasks keep their priority as `pri:`, as the format suggests.
+  fn render(&self, today: NaiveDate) -> String {
+    let created = self.created.as_ref().map(|v| format!("{v} ")).unwrap_or_default();
+
+    match (self.done, self.priority) {
+      (true, Some(p)) => format!("x {today} {created}{} pri:{p}", self.description),
+      (true, None) => format!("x {today} {created}{}", self.description),
+      (false, Some(p)) => format!("({p}) {created}{}", self.description),
+      (false, None) => format!("{created}{}", self.description),
+    }
+  }
+
+  fn item(&self) -> Item {
+    let (title, mut tags) = describe(&self.description);
+    if let Some(p) = self.priority {
+      tags.insert(0, format!("pri:{p}"));
+    }
+
+    Item {
+      key: self.description.clone(),
+      occurrence: 0,
+      title,
+      tags,
+      done: self.done,
+    }
+  }
+}
+
+/// Splits `+project` and `@context` off the title, the project loses its `+`.
+fn describe(text: &str) -> (String, Vec<String>) {
+  let mut title = Vec::new();
+  let mut tags = Vec::new();
+
+  for word in text.split_whitespace() {
+    match (word.strip_prefix('+'), word.starts_with('@') && word.len() > 1) {
+      (Some(project), _) if !project.is_empty() => tags.push(project.to_owned()),
+      (_, true) => tags.push(word.to_owned()),
+      _ => title.push(word),
+    }
+  }
+
+  (title.join(" "), tags)
+}
+
+fn date(text: &str) -> Option<(&str, &str)> {
+  let (word, rest) = text.split_once(' ').unwrap_or((text, ""));
+  NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()?;
+  Some((word, rest.trim_start()))
+}
+
+fn priority_of(text: &str) -> Option<(char, &str)> {
+  let rest = text.strip_prefix('(')?;
+  let p = single_upper(rest.get(..1)?)?;
+  let rest = rest[1..].strip_prefix(") ")?;
+  Some((p, rest.trim_start()))
+}
+
+fn single_upper(text: &str) -> Option<char> {
+  let mut chars = text.chars();
+  match (chars.next(), chars.next()) {
+    (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+    _ => None,
+  }
+}
+
+/// Splits a Markdown checklist line into indent and bullet, whether it is checked, and the text.
+fn checklist(line: &str) -> Option<(&str, bool, &str)> {
+  let text = line.trim_start();
+  let indent = &line[..line.len() - text.len()];
+
+  let rest = ["- ", "* ", "+ "].iter().find_map(|v| text.strip_prefix(v))?;
+  let (done, rest) = match rest.get(..4)? {
+    "[ ] " => (false, &rest[4..]),
+    "[x] " | "[X] " => (true, &rest[4..]),
+    _ => return None,
+  };
+
+  let bullet = &line[..indent.len() + 2];
+  Some((bullet, done, rest.trim()))
+}
+
+pub fn parse(format: Format, content: &str) -> Vec<Item> {
+  let mut items: Vec<Item> = match format {
+    Format::TodoTxt => content.lines().filter_map(Line::parse).map(|v| v.item()).collect(),
+    Format::Markdown => content
+      .lines()
+      .filter_map(checklist)
+      .filter_map(|(_, done, text)| {
+        let line = Line::parse(text)?;
+        Some(Item { done, ..line.item() })
+      })
+      .collect(),
+  };
+
+  let mut seen = HashMap::new();
+  for item in &mut items {
+    let n = seen.entry(item.key.clone()).or_insert(0);
+    item.occurrence = *n;
+    *n += 1;
+  }
+  items
+}
+
+/// `line` checked or unchecked, `None` if it does not hold the task `key`.
+fn toggle(format: Format, line: &str, key: &str, done: bool, today: NaiveDate) -> Option<String> {
+  match format {
+    Format::TodoTxt => {
+      let mut v = Line::parse(line).filter(|v| v.description == key)?;
+      v.done = done;
+      Some(v.render(today))
+    }
+    Format::Markdown => {
+      let (bullet, _, text) = checklist(line)?;
+      Line::parse(text).filter(|v| v.description == key)?;
+      Some(format!("{bullet}[{}] {text}", if done { 'x' } else { ' ' }))
+    }
+  }
+}
+
+/// `content` with the `occurrence`th task `key` checked or unchecked, `None` if it is not in there.
+///
+/// Every other line is kept byte for byte, line endings included.
+pub fn set_done(
+  format: Format,
+  content: &str,
+  key: &str,
+  occurrence: usize,
+  done: bool,
+  today: NaiveDate,
+) -> Option<String> {
+  let mut seen = 0;
+  let mut found = false;
+  let mut out = String::with_capacity(content.len() + 16);
+
+  for segment in content.split_inclusive('\n') {
+    let line = segment.trim_end_matches(['\r', '\n']);
+
+    match (!found).then(|| toggle(format, line, key, done, today)).flatten() {
+      Some(v) if seen == occurrence => {
+        found = true;
+        out.push_str(&v);
+      }
+      Some(_) => {
+        seen += 1;
+        out.push_str(line);
+      }
+      None => out.push_str(line),
+    }
+    out.push_str(&segment[line.len()..]);
+  }
+
+  found.then_some(out)
+}
+
+pub fn read(path: &Path) -> Result<Vec<Item>, String> {
+  let content = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+  Ok(parse(Format::from_path(path), &content))
+}
+
+pub fn write_back(path: &Path, key: &str, occurrence: usize, done: bool, today: NaiveDate) -> Result<(), String> {
+  // two quick toggles read and rewrite the file one after the other
+  static WRITING: Mutex<()> = Mutex::new(());
+  let _writing = WRITING.lock().map_err(|e| e.to_string())?;
+  let error = |e: std::io::Error| format!("{}: {e}", path.display());
+
+  let content = fs::read_to_string(path).map_err(error)?;
+  let content = set_done(Format::from_path(path), &content, key, occurrence, done, today)
+    .ok_or_else(|| format!("{}: task is no longer there", path.display()))?;
+
+  // an editor or sync tool never sees a half written file
+  let name = path.file_name().map(|v| v.to_string_lossy()).unwrap_or_default();
+  let temp = path.with_file_name(format!(".{name}.tmp"));
+  fs::write(&temp, content).and_then(|_| fs::rename(&temp, path)).map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 2, 14).unwrap()
+  }
+
+  #[test]
+  fn todo_txt_tokens_become_tags() {
+    let items = parse(
+      Format::TodoTxt,
+      "(A) 2024-02-01 Call mom +family @phone\nx 2024-02-10 2024-02-01 Pay rent +home pri:B\n\n",
+    );
+
+    assert_eq!(
+      items,
+      [
+        Item {
+          key: "Call mom +family @phone".to_owned(),
+          occurrence: 0,
+          title: "Call mom".to_owned(),
+          tags: vec!["pri:A".to_owned(), "family".to_owned(), "@phone".to_owned()],
+          done: false,
+        },
+        Item {
+          key: "Pay rent +home".to_owned(),
+          occurrence: 0,
+          title: "Pay rent".to_owned(),
+          tags: vec!["pri:B".to_owned(), "home".to_owned()],
+          done: true,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn todo_txt_write_back_round_trips() {
+    let content = "(A) 2024-02-01 Call mom +family\nWater plants\n";
+
+    let done = set_done(Format::TodoTxt, content, "Call mom +family", 0, true, today()).unwrap();
+    assert_eq!(done, "x 2024-02-14 2024-02-01 Call mom +family pri:A\nWater plants\n");
+    assert_eq!(parse(Format::TodoTxt, &done)[0].key, "Call mom +family");
+
+    let open = set_done(Format::TodoTxt, &done, "Call mom +family", 0, false, today()).unwrap();
+    assert_eq!(open, content);
+
+    assert!(set_done(Format::TodoTxt, content, "Feed cat", 0, true, today()).is_none());
+  }
+
+  #[test]
+  fn markdown_checklists() {
+    let content = "# Today\n\n- [ ] Write report +work\n  * [x] Outline\nplain text\n";
+
+    let items = parse(Format::Markdown, content);
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].title, "Write report");
+    assert_eq!(items[0].tags, vec!["work"]);
+    assert!(items[1].done);
+
+    let done = set_done(Format::Markdown, content, "Write report +work", 0, true, today()).unwrap();
+    assert_eq!(done, "# Today\n\n- [x] Write report +work\n  * [x] Outline\nplain text\n");
+  }
+
+  #[test]
+  fn duplicates_and_line_endings() {
+    let content = "Water plants\r\n(B) Water plants\r\nPay rent\r\n\r\n  trailing  ";
+
+    let items = parse(Format::TodoTxt, content);
+    assert_eq!(items.iter().map(|v| v.occurrence).collect::<Vec<_>>(), [0, 1, 0, 0]);
+
+    let done = set_done(Format::TodoTxt, content, "Water plants", 1, true, today()).unwrap();
+    assert_eq!(
+      done,
+      "Water plants\r\nx 2024-02-14 Water plants pri:B\r\nPay rent\r\n\r\n  trailing  "
+    );
+    assert!(set_done(Format::TodoTxt, content, "Water plants", 2, true, today()).is_none());
+  }
+}
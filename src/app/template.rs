@@ -0,0 +1,180 @@
+use super::timer::{ticker::Phase, Timer, TimerEvent};
+
+pub const PLACEHOLDERS: &[&str] = &[
+  "phase",
+  "next_phase",
+  "next_time",
+  "cycle",
+  "timer_name",
+  "elapsed_today",
+  "task",
+  "activity",
+];
+
+/// Values substituted into notification templates.
+#[derive(Debug, Clone, Default)]
+pub struct Vars {
+  pub phase: String,
+  pub next_phase: String,
+  pub next_time: String,
+  pub cycle: u32,
+  pub timer_name: String,
+  pub elapsed_today: String,
+  pub task: String,
+  pub activity: String,
+}
+
+impl Vars {
+  pub fn new(timer: &Timer, event: TimerEvent) -> Self {
+    // the ticker has already moved on when a phase ended
+    let (phase, next_phase) = match event {
+      TimerEvent::WorkEnd => (Phase::Work.to_string(), timer.phase().to_string()),
+      TimerEvent::BreakEnd => (Phase::Break.to_string(), timer.phase().to_string()),
+      TimerEvent::Warning => (timer.phase().to_string(), timer.next_phase().to_string()),
+    };
+
+    let next_time = timer
+      .deadline()
+      .map(|v| v.format("%H:%M").to_string())
+      .unwrap_or_default();
+
+    let elapsed_today = timer.focused_today().as_secs();
+
+    Self {
+      phase,
+      next_phase,
+      next_time,
+      cycle: timer.cycle,
+      timer_name: timer.ticker.name.to_owned(),
+      elapsed_today: format!("{}h{:02}m", elapsed_today / 3600, elapsed_today % 3600 / 60),
+      task: String::new(),
+      activity: String::new(),
+    }
+  }
+
+  fn get(&self, name: &str) -> Option<String> {
+    Some(match name {
+      "phase" => self.phase.clone(),
+      "next_phase" => self.next_phase.clone(),
+      "next_time" => self.next_time.clone(),
+      "cycle" => self.cycle.to_string(),
+      "timer_name" => self.timer_name.clone(),
+      "elapsed_today" => self.elapsed_today.clone(),
+      "task" => self.task.clone(),
+      "activity" => self.activity.clone(),
+      _ => return None,
+    })
+  }
+}
+
+enum Part<'a> {
+  Text(&'a str),
+  Placeholder(&'a str),
+}
+
+/// Splits `template` into text and `{name}` placeholders, `{{name}}` being a literal `{name}`.
+///
+/// Any other brace is plain text, so JSON bodies need no escaping.
+fn parse(template: &str) -> Vec<Part<'_>> {
+  let mut parts = Vec::new();
+  let mut rest = template;
+
+  while let Some(start) = rest.find('{') {
+    let (text, tail) = rest.split_at(start);
+    parts.push(Part::Text(text));
+
+    if let Some(name) = placeholder(&tail[1..]).filter(|v| tail[v.len() + 3..].starts_with('}')) {
+      // `{{name}}` is the literal `{name}`
+      parts.push(Part::Text(&tail[1..name.len() + 3]));
+      rest = &tail[name.len() + 4..];
+    } else if let Some(name) = placeholder(tail) {
+      parts.push(Part::Placeholder(name));
+      rest = &tail[name.len() + 2..];
+    } else {
+      parts.push(Part::Text(&tail[..1]));
+      rest = &tail[1..];
+    }
+  }
+  parts.push(Part::Text(rest));
+
+  parts
+}
+
+/// The identifier of a `{name}` placeholder at the start of `text`.
+fn placeholder(text: &str) -> Option<&str> {
+  let name = text.strip_prefix('{')?;
+  let end = name.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+  (end > 0 && name[end..].starts_with('}')).then(|| &name[..end])
+}
+
+pub fn render(template: &str, vars: &Vars) -> String {
+  render_with(template, |name| vars.get(name))
+}
+
+/// Renders `template` with values looked up by `get`, leaving unknown placeholders as they are.
+pub fn render_with(template: &str, get: impl Fn(&str) -> Option<String>) -> String {
+  parse(template)
+    .into_iter()
+    .map(|part| match part {
+      Part::Text(text) => text.to_owned(),
+      Part::Placeholder(name) => get(name).unwrap_or_else(|| format!("{{{name}}}")),
+    })
+    .collect()
+}
+
+/// Placeholders in `template` that [`render`] does not know.
+pub fn unknown(template: &str) -> Vec<String> {
+  unknown_in(template, PLACEHOLDERS)
+}
+
+/// Placeholders in `template` missing from `known`.
+pub fn unknown_in(template: &str, known: &[&str]) -> Vec<String> {
+  parse(template)
+    .into_iter()
+    .filter_map(|part| match part {
+      Part::Placeholder(name) if !known.contains(&name) => Some(name.to_owned()),
+      _ => None,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_placeholders() {
+    let vars = Vars {
+      phase: "work".to_owned(),
+      next_phase: "break".to_owned(),
+      next_time: "12:30".to_owned(),
+      cycle: 3,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      render("{phase} done, {next_phase} until {next_time} (#{cycle})", &vars),
+      "work done, break until 12:30 (#3)"
+    );
+    assert_eq!(render("{{phase}} {unknown} {", &vars), "{phase} {unknown} {");
+  }
+
+  #[test]
+  fn json_braces_are_text() {
+    let vars = Vars {
+      phase: "work".to_owned(),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      render(r#"{"text": "{phase}", "nested": {"a": {}}}"#, &vars),
+      r#"{"text": "work", "nested": {"a": {}}}"#
+    );
+    assert!(unknown(r#"{"a": {"b": 1}}, { phase }"#).is_empty());
+  }
+
+  #[test]
+  fn unknown_placeholders() {
+    assert_eq!(unknown("{phase} {phaze} {{task}} {task}"), vec!["phaze"]);
+  }
+}
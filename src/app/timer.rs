@@ -5,8 +5,14 @@ use std::{
   time::Duration,
 };
 
-use chrono::NaiveDateTime;
-use ticker::Ticker;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use ticker::{Phase, Ticker, TickerState};
+
+use super::config::{self, Hms};
+
+// how long after a transition a snooze still undoes it without a pending notification
+const SNOOZE_GRACE: chrono::Duration = chrono::Duration::minutes(2);
 
 #[derive(Default)]
 pub struct Timer {
@@ -14,9 +20,221 @@ pub struct Timer {
   pub data: Data,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerEvent {
+  WorkEnd,
+  BreakEnd,
+  // `Data::warning` before the end of a phase
+  Warning,
+}
+
 impl Timer {
-  pub fn tick(&mut self) -> bool {
-    (self.ticker.logic)(&mut self.data, &mut self.ticker.state)
+  pub fn tick(&mut self) -> Option<TimerEvent> {
+    let phase = self.phase();
+    let started = self
+      .data
+      .started
+      .or_else(|| self.data.next.map(|next| next - self.current_duration()));
+
+    let state = self.ticker.state;
+    if (self.ticker.logic)(&mut self.data, &mut self.ticker.state) {
+      self.data.previous = Some((state, Local::now().naive_local()));
+      self.data.cycle += 1;
+      self.data.warned = false;
+      self.data.started = None;
+      // snoozes are counted until a phase ends without being snoozed back into
+      if !std::mem::take(&mut self.data.reopened) {
+        self.data.snoozes = 0;
+      }
+
+      if phase == Phase::Work {
+        let now = Local::now().naive_local();
+        let focused = started.map(|v| (now - v).to_std().unwrap_or_default()).unwrap_or_default();
+        self.add_focused(now.date(), focused);
+      }
+
+      return Some(match phase {
+        Phase::Work => TimerEvent::WorkEnd,
+        Phase::Break => TimerEvent::BreakEnd,
+      });
+    }
+
+    if let (Some(warning), Some(remaining)) = (self.data.warning, self.remaining()) {
+      if self.data.enable && !self.data.warned && remaining <= warning {
+        self.data.warned = true;
+        return Some(TimerEvent::Warning);
+      }
+    }
+
+    None
+  }
+
+  /// Goes back to the phase that ended last and ends it again after `duration`, returns whether it did.
+  ///
+  /// Only a transition that just happened, or whose notification is still `pending`, is undone.
+  /// Otherwise the current phase is extended instead.
+  pub fn snooze(&mut self, duration: Duration, pending: bool) -> bool {
+    self.data.snoozes += 1;
+
+    let now = Local::now().naive_local();
+    let previous = match self.data.previous.take() {
+      Some((previous, at)) if pending || now - at < SNOOZE_GRACE => previous,
+      _ => {
+        self.extend(duration);
+        return false;
+      }
+    };
+
+    self.ticker.state = previous;
+    // the phase is counted again when the snooze ends, but only the snooze adds to the focused time
+    self.data.cycle = self.data.cycle.saturating_sub(1);
+    self.data.started = Some(now);
+    self.data.next = Some(now + duration);
+    self.data.warned = false;
+    self.data.reopened = true;
+    true
+  }
+
+  /// Forgets the last transition once it was acknowledged, so a later snooze does not undo it.
+  pub fn settle(&mut self) {
+    self.data.previous = None;
+  }
+
+  /// Pushes the end of the current phase back by `duration`.
+  pub fn extend(&mut self, duration: Duration) {
+    match self.data.next {
+      Some(next) => {
+        let started = next - self.current_duration();
+        self.data.started.get_or_insert(started);
+        self.data.next = Some(next + duration);
+      }
+      None => {
+        if let Some(remaining) = &mut self.data.remaining {
+          *remaining += duration;
+        }
+      }
+    }
+    self.data.warned = false;
+  }
+
+  /// Ends the current phase now without reporting an event.
+  pub fn skip(&mut self) {
+    if !self.data.enable {
+      return;
+    }
+
+    let now = Local::now().naive_local();
+    self.data.next = Some(now - Duration::from_millis(1));
+    (self.ticker.logic)(&mut self.data, &mut self.ticker.state);
+    self.data.previous = None;
+    self.data.warned = false;
+    self.data.started = None;
+    self.data.reopened = false;
+    self.data.snoozes = 0;
+  }
+
+  /// Starts the current phase over from now.
+  pub fn restart_phase(&mut self) {
+    if !self.data.enable {
+      return;
+    }
+
+    self.data.next = Some(Local::now().naive_local() + self.current_duration());
+    self.data.previous = None;
+    self.data.warned = false;
+    self.data.started = None;
+  }
+
+  pub fn phase(&self) -> Phase {
+    (self.ticker.phase)(&self.ticker.state)
+  }
+
+  pub fn next_phase(&self) -> Phase {
+    (self.ticker.next_phase)(&self.ticker.state)
+  }
+
+  /// Length of the current phase.
+  pub fn current_duration(&self) -> Duration {
+    (self.ticker.duration)(&self.data, &self.ticker.state)
+  }
+
+  /// Time spent in work phases today.
+  pub fn focused_today(&self) -> Duration {
+    match self.data.focused {
+      Some((date, focused)) if date == Local::now().date_naive() => focused,
+      _ => Duration::ZERO,
+    }
+  }
+
+  fn add_focused(&mut self, date: NaiveDate, focused: Duration) {
+    let total = match self.data.focused {
+      Some((v, total)) if v == date => total,
+      _ => Duration::ZERO,
+    };
+    self.data.focused = Some((date, total + focused));
+  }
+
+  /// Returns when the ticker is due next, if it is running.
+  pub fn deadline(&self) -> Option<NaiveDateTime> {
+    self.data.enable.then_some(self.data.next).flatten()
+  }
+
+  /// Returns when the warning before the end of the phase is due, if it has not been sent yet.
+  pub fn warning_deadline(&self) -> Option<NaiveDateTime> {
+    let warning = self.data.warning?;
+    (!self.data.warned).then_some(self.deadline()? - warning)
+  }
+
+  pub fn remaining(&self) -> Option<Duration> {
+    let now = Local::now().naive_local();
+
+    match self.deadline() {
+      Some(next) => Some((next - now).to_std().unwrap_or_default()),
+      None => self.data.remaining,
+    }
+  }
+
+  pub fn pause(&mut self) {
+    let now = Local::now().naive_local();
+
+    self.data.enable = false;
+    self.data.previous = None;
+    if let Some(next) = self.data.next.take() {
+      self.data.remaining = Some((next - now).to_std().unwrap_or_default());
+    }
+  }
+
+  pub fn resume(&mut self) {
+    let now = Local::now().naive_local();
+
+    self.data.enable = true;
+    self.data.previous = None;
+    if let Some(remaining) = self.data.remaining.take() {
+      self.data.next = Some(now + remaining);
+    }
+  }
+
+  pub fn snapshot(&self) -> TimerState {
+    TimerState {
+      ticker: self.ticker.clone(),
+      phase: self.ticker.state,
+      cycle: self.data.cycle,
+      enable: self.data.enable,
+      next: self.data.next,
+      remaining: self.data.remaining.map(Hms::from),
+      snoozes: self.data.snoozes,
+    }
+  }
+
+  pub fn restore(&mut self, state: TimerState) {
+    self.ticker = state.ticker;
+    self.ticker.state = state.phase;
+    self.data.cycle = state.cycle;
+    self.data.enable = state.enable;
+    self.data.next = state.next;
+    self.data.remaining = state.remaining.map(Duration::from);
+    self.data.snoozes = state.snoozes;
   }
 }
 
@@ -39,6 +257,20 @@ pub struct Data {
   pub enable: bool,
   pub duration: Duration,
   pub next: Option<NaiveDateTime>,
+  // time left when paused
+  pub remaining: Option<Duration>,
+  pub cycle: u32,
+  pub warning: Option<Duration>,
+  pub warned: bool,
+  // ticker state before the last transition and when it happened
+  pub previous: Option<(TickerState, NaiveDateTime)>,
+  // snoozes of the current session
+  pub snoozes: u32,
+  // the phase was snoozed back into after it ended
+  pub reopened: bool,
+  // start of the current phase when it was moved by a snooze
+  pub started: Option<NaiveDateTime>,
+  pub focused: Option<(NaiveDate, Duration)>,
 }
 
 impl Default for Data {
@@ -47,6 +279,146 @@ impl Default for Data {
       enable: true,
       duration: Duration::from_secs(60 * 30),
       next: None,
+      remaining: None,
+      cycle: 0,
+      warning: None,
+      warned: false,
+      previous: None,
+      snoozes: 0,
+      reopened: false,
+      started: None,
+      focused: None,
+    }
+  }
+}
+
+/// Serializable form of a running [`Timer`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TimerState {
+  #[serde(with = "config::ticker")]
+  pub ticker: Ticker,
+  pub phase: TickerState,
+  pub cycle: u32,
+  pub enable: bool,
+  pub next: Option<NaiveDateTime>,
+  pub remaining: Option<Hms>,
+  #[serde(default)]
+  pub snoozes: u32,
+}
+
+#[cfg(test)]
+mod tests {
+  use iced::futures::StreamExt;
+
+  use super::*;
+
+  #[test]
+  fn warning_fires_before_the_end() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_time()
+      .build()
+      .unwrap();
+
+    let mut timer = Timer {
+      data: Data {
+        duration: Duration::from_secs(2),
+        warning: Some(Duration::from_secs(1)),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    assert_eq!(timer.tick(), None);
+
+    // wake up the way the app does, at whichever subscription deadline comes first
+    let mut events = Vec::new();
+    while !events.contains(&TimerEvent::WorkEnd) {
+      let at = [timer.deadline(), timer.warning_deadline()].into_iter().flatten().min().unwrap();
+      runtime.block_on(Box::pin(crate::subscription::wait_until(at)).next());
+      events.extend(timer.tick());
+    }
+
+    assert_eq!(events, [TimerEvent::Warning, TimerEvent::WorkEnd]);
+  }
+
+  #[test]
+  fn snooze_extends_or_reopens() {
+    let mut timer = Timer {
+      ticker: ticker::Ticker::from_name("Pomodoro").unwrap(),
+      ..Default::default()
+    };
+    timer.tick();
+
+    // in the middle of a phase the end is pushed back
+    let next = timer.next.unwrap();
+    assert!(!timer.snooze(Duration::from_secs(300), false));
+    assert_eq!(timer.next, Some(next + Duration::from_secs(300)));
+    assert_eq!(timer.phase(), Phase::Work);
+
+    // right after the end the phase is reopened for the snooze only
+    timer.next = Some(Local::now().naive_local() - Duration::from_secs(1));
+    assert_eq!(timer.tick(), Some(TimerEvent::WorkEnd));
+    assert_eq!((timer.cycle, timer.snoozes), (1, 0));
+    let focused = timer.focused_today();
+
+    assert!(timer.snooze(Duration::from_secs(300), false));
+    assert_eq!((timer.phase(), timer.cycle, timer.snoozes), (Phase::Work, 0, 1));
+
+    timer.next = Some(Local::now().naive_local() - Duration::from_secs(1));
+    assert_eq!(timer.tick(), Some(TimerEvent::WorkEnd));
+    assert_eq!((timer.cycle, timer.snoozes), (1, 1));
+    assert!(timer.focused_today() - focused < Duration::from_secs(60));
+  }
+
+  #[test]
+  fn snooze_later_in_the_phase_extends() {
+    let mut timer = Timer {
+      ticker: ticker::Ticker::from_name("Pomodoro").unwrap(),
+      ..Default::default()
+    };
+    timer.tick();
+    for _ in 0..2 {
+      timer.next = Some(Local::now().naive_local() - Duration::from_secs(1));
+      timer.tick();
     }
+    assert_eq!((timer.phase(), timer.cycle), (Phase::Work, 2));
+
+    // ten minutes into the phase the transition is no longer undone
+    let (state, _) = timer.previous.unwrap();
+    timer.previous = Some((state, Local::now().naive_local() - chrono::Duration::minutes(10)));
+    let next = timer.next.unwrap();
+    assert!(!timer.snooze(Duration::from_secs(300), false));
+    assert_eq!((timer.phase(), timer.cycle), (Phase::Work, 2));
+    assert_eq!(timer.next, Some(next + Duration::from_secs(300)));
+
+    // unless its notification is still pending
+    timer.next = Some(Local::now().naive_local() - Duration::from_secs(1));
+    timer.tick();
+    let (state, _) = timer.previous.unwrap();
+    timer.previous = Some((state, Local::now().naive_local() - chrono::Duration::minutes(10)));
+    assert!(timer.snooze(Duration::from_secs(300), true));
+    assert_eq!((timer.phase(), timer.cycle), (Phase::Work, 2));
+
+    // an acknowledged transition stays
+    timer.next = Some(Local::now().naive_local() - Duration::from_secs(1));
+    timer.tick();
+    timer.settle();
+    assert!(!timer.snooze(Duration::from_secs(300), true));
+    assert_eq!((timer.phase(), timer.cycle), (Phase::Break, 3));
+  }
+
+  #[test]
+  fn snooze_postpones_the_normal_ticker() {
+    let mut timer = Timer::default();
+    timer.tick();
+    timer.next = Some(Local::now().naive_local() - Duration::from_secs(1));
+    assert_eq!(timer.tick(), Some(TimerEvent::WorkEnd));
+
+    // a snooze in the middle of the next phase moves its end back instead of ending it early
+    let (state, _) = timer.previous.unwrap();
+    timer.previous = Some((state, Local::now().naive_local() - chrono::Duration::minutes(10)));
+    let next = timer.next.unwrap();
+    assert!(!timer.snooze(Duration::from_secs(300), false));
+    assert_eq!(timer.next, Some(next + Duration::from_secs(300)));
+    assert_eq!(timer.cycle, 1);
   }
 }
@@ -4,6 +4,10 @@ mod pomodoro;
 use normal::Normal;
 use pomodoro::Pomodoro;
 
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
 use super::Data;
 
 pub type TickerState = u32;
@@ -12,11 +16,34 @@ pub type TickerState = u32;
 pub struct Ticker {
   pub name: &'static str,
   pub logic: fn(&mut Data, &mut u32) -> bool,
+  pub phase: fn(&TickerState) -> Phase,
+  pub next_phase: fn(&TickerState) -> Phase,
+  pub duration: fn(&Data, &TickerState) -> Duration,
   pub state: TickerState,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+  Work,
+  Break,
+}
+
+impl fmt::Display for Phase {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Phase::Work => write!(f, "work"),
+      Phase::Break => write!(f, "break"),
+    }
+  }
+}
+
 impl Ticker {
   pub const ALL_TICKER: &[Ticker] = &[wrap::<Normal>(), wrap::<Pomodoro>()];
+
+  pub fn from_name(name: &str) -> Option<Ticker> {
+    Self::ALL_TICKER.iter().find(|v| v.name == name).cloned()
+  }
 }
 
 impl Default for Ticker {
@@ -34,12 +61,27 @@ impl ToString for Ticker {
 pub trait TickerBase {
   const NAME: &'static str;
   fn tick(timer: &mut Data, state: &mut u32) -> bool;
+
+  /// Length of the current phase.
+  fn duration(data: &Data, state: &TickerState) -> Duration;
+
+  fn phase(_state: &TickerState) -> Phase {
+    Phase::Work
+  }
+
+  /// Phase that follows the current one.
+  fn next_phase(_state: &TickerState) -> Phase {
+    Phase::Work
+  }
 }
 
 const fn wrap<T: TickerBase>() -> Ticker {
   Ticker {
     name: T::NAME,
     logic: T::tick,
+    phase: T::phase,
+    next_phase: T::next_phase,
+    duration: T::duration,
     state: 0,
   }
 }
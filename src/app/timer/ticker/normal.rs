@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use chrono::{format::StrftimeItems, Local};
 
 use super::*;
@@ -28,4 +30,8 @@ impl TickerBase for Normal {
     }
     false
   }
+
+  fn duration(data: &Data, _state: &TickerState) -> Duration {
+    data.duration
+  }
 }
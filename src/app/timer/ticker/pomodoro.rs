@@ -42,6 +42,24 @@ impl TickerBase for Pomodoro {
     }
     false
   }
+
+  fn duration(_data: &Data, state: &TickerState) -> Duration {
+    mode_to_min(Self::phase(state) == Phase::Break)
+  }
+
+  fn phase(state: &TickerState) -> Phase {
+    match *state {
+      1 => Phase::Break,
+      _ => Phase::Work,
+    }
+  }
+
+  fn next_phase(state: &TickerState) -> Phase {
+    match Self::phase(state) {
+      Phase::Work => Phase::Break,
+      Phase::Break => Phase::Work,
+    }
+  }
 }
 
 fn is_break(state: &mut u32) -> bool {
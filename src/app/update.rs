@@ -9,19 +9,54 @@ use iced::{
 use tray_icon::{MouseButton, MouseButtonState, TrayIconEvent};
 
 use super::{
-  config::{load, save, ConfigEvent, Hms},
+  activity,
+  config::{check, load, save, ConfigEvent, Hms},
+  counter,
+  event::{self, EventKind},
+  export,
+  history::{self, Outcome},
   info::{self, Info},
-  App, Message,
+  journal::JournalEvent,
+  mqtt::{self, Command, MqttEvent},
+  notify::{self, action::Action},
+  overlay,
+  sound, state, stats, task,
+  timer::{ticker::Phase, TimerEvent},
+  webhook, App, Escalating, Message, Page,
 };
 
 pub(crate) fn update(app: &mut App, message: Message) -> Task<Message> {
   match message {
     Message::Tick => {
-      if app.timer.tick() {
-        println!("elapsed!");
-        return Task::done(Message::Notify);
+      let reminders = counter::tick(app);
+      let event = app.timer.tick();
+      let mut written = Task::none();
+      if event.is_some_and(|v| v != TimerEvent::Warning) {
+        written = history::finish(app, Outcome::Completed);
+        if event == Some(TimerEvent::WorkEnd) && app.tasks.complete_pomodoro().is_some() {
+          written = Task::batch([written, task::save(app)]);
+        }
       }
+      history::begin(app);
+
+      if let Some(event) = event {
+        println!("{event:?}");
+        if event != TimerEvent::Warning {
+          state::record(app, JournalEvent::Phase);
+        }
+        if event == TimerEvent::WorkEnd && app.timer.phase() == Phase::Break {
+          app.activity = app.suggestions.next();
+        }
+        return Task::batch([
+          reminders,
+          written,
+          Task::done(Message::Notify(event)),
+          event::emit(app, event.into()),
+        ]);
+      }
+      return Task::batch([reminders, written]);
     }
+    Message::Refresh => (),
     Message::WindowEvent((e, id)) => match e {
       window::Event::Opened { .. } => {
         return Task::batch([
@@ -29,12 +64,17 @@ pub(crate) fn update(app: &mut App, message: Message) -> Task<Message> {
           Task::done(Message::Tick),
         ])
       }
-      window::Event::Closed => app.window = None,
+      window::Event::Closed if app.overlay == Some(id) => {
+        app.overlay = None;
+        // closing the overlay counts as skipping it, unless skipping is not allowed
+        app.overlay_dismissed = !app.config.overlay.as_ref().is_some_and(|v| v.strict);
+      }
+      window::Event::Closed if app.window == Some(id) => app.window = None,
       // TODO
-      window::Event::Moved(point) => {
+      window::Event::Moved(point) if app.window == Some(id) => {
         app.window_pos = Some(point);
       }
-      window::Event::Unfocused => return window::close(id),
+      window::Event::Unfocused if app.window == Some(id) => return window::close(id),
       _ => (),
     },
     Message::WindowCreateRequested => {
@@ -74,8 +114,20 @@ pub(crate) fn update(app: &mut App, message: Message) -> Task<Message> {
     }
     Message::TrayMenuEvent(id) => match id.0.as_str() {
       App::SHOW_ID => return Task::done(Message::WindowCreateRequested),
-      App::QUIT_ID => return iced::exit(),
-      _ => (),
+      App::SNOOZE_ID => return Task::done(Message::Snooze),
+      App::ACKNOWLEDGE_ID => return Task::done(Message::Acknowledge),
+      App::QUIT_ID => {
+        // nothing runs after the exit, so the history is flushed right here
+        let _ = history::finish(app, Outcome::Interrupted);
+        history::close(app);
+        state::close(app);
+        return iced::exit();
+      }
+      id => {
+        if let Some(name) = id.strip_prefix(App::COUNT_ID) {
+          return Task::done(Message::Count(name.to_owned()));
+        }
+      }
     },
     #[allow(clippy::single_match)]
     Message::TrayIconEvent(e) => {
@@ -96,11 +148,15 @@ pub(crate) fn update(app: &mut App, message: Message) -> Task<Message> {
       }
       ConfigEvent::Load => {
         load(app);
-        return info::send("config loaded");
+        return Task::batch(
+          [info::send("config loaded"), task::import_all(app)]
+            .into_iter()
+            .chain(check(&app.config).into_iter().map(info::send)),
+        );
       }
     },
     Message::ChangeConfig(msg) => match msg {
-      super::config::ChangeConfig::CheckRate(v) => app.config.check_rate = Hms::ZERO.second(v),
+      super::config::ChangeConfig::CheckRate(v) => app.config.check_rate = Some(Hms::ZERO.second(v)),
       super::config::ChangeConfig::Duration(duration) => {
         dbg!(duration);
       }
@@ -110,7 +166,15 @@ pub(crate) fn update(app: &mut App, message: Message) -> Task<Message> {
         return Task::done(Message::Pause(true));
       }
     },
-    Message::ChangePage(page) => app.page = page,
+    Message::ChangePage(page) => {
+      if let Page::Stats = page {
+        stats::refresh(app);
+      }
+      app.page = page;
+    }
+    Message::Stats(message) => stats::update(app, message),
+    Message::Export(format) => return export::export(app, format),
+    Message::Task(message) => return task::update(app, message),
     Message::Info(info) => match info {
       Info::Send(text) => {
         app.info = Some(text);
@@ -136,14 +200,186 @@ pub(crate) fn update(app: &mut App, message: Message) -> Task<Message> {
       Info::Clear => app.info = None,
     },
     Message::Pause(stopped) => {
-      app.timer.enable = !stopped;
+      let mut recorded = Task::none();
+      let kind = if stopped {
+        recorded = history::finish(app, Outcome::Interrupted);
+        app.timer.pause();
+        state::record(app, JournalEvent::Pause);
+        EventKind::Pause
+      } else {
+        app.timer.resume();
+        state::record(app, JournalEvent::Resume);
+        EventKind::Resume
+      };
+      return Task::batch([recorded, Task::done(Message::Tick), event::emit(app, kind)]);
+    }
+    Message::Snooze => {
+      // a snooze answers the escalating notification of the transition it undoes
+      let pending = app.escalation.take().is_some();
+      if !app.timer.enable {
+        return info::send("timer is paused");
+      }
+      let (mut saved, mut recorded) = (Task::none(), Task::none());
+      // a snooze that only extends the phase keeps its session
+      if app.timer.snooze((&app.config.snooze).into(), pending) {
+        if app.timer.phase() == Phase::Work {
+          // the reopened work phase is counted again when it ends
+          app.tasks.undo_pomodoro();
+          saved = task::save(app);
+        }
+        recorded = history::transition(app, Outcome::Interrupted);
+      }
+      state::record(app, JournalEvent::Phase);
+      return Task::batch([
+        saved,
+        recorded,
+        info::send(format!("snoozed for {} min", app.config.snooze.as_minutes())),
+        event::emit(app, EventKind::Snooze),
+      ]);
+    }
+    Message::Skip => {
+      // a paused timer does not skip, so there is no skip to record or announce
+      if !app.timer.enable {
+        return info::send("timer is paused");
+      }
+      app.timer.skip();
+      let recorded = history::transition(app, Outcome::Skipped);
+      state::record(app, JournalEvent::Phase);
+      return Task::batch([recorded, event::emit(app, EventKind::Skip)]);
+    }
+    Message::Postpone => {
+      let Some(config) = &app.config.overlay else {
+        return Task::none();
+      };
+      if overlay::postpones_left(app) == 0 {
+        return info::send("no postpones left today");
+      }
 
-      if stopped {
-        app.timer.next = None;
+      let duration = (&config.postpone).into();
+      overlay::postpone(app, duration);
+      state::record(app, JournalEvent::Phase);
+      return event::emit(app, EventKind::Postpone);
+    }
+    Message::ActivityDone => {
+      if let Some(activity) = app.activity.take() {
+        return match activity::log(&activity) {
+          Ok(()) => info::send(format!("done: {}", activity.title)),
+          Err(e) => info::send(format!("failed to log activity: {e}")),
+        };
+      }
+    }
+    Message::Count(name) => return counter::increment(app, &name),
+    Message::Resume(resume) => {
+      if let Some(saved) = app.resume.take() {
+        if resume {
+          app.timer.restore(saved);
+          state::record(app, JournalEvent::Restore);
+        } else {
+          state::record(app, JournalEvent::Start);
+        }
+        return Task::done(Message::Tick);
       }
-      return Task::done(Message::Tick);
     }
-    Message::Notify => app.notification.show().unwrap(),
+    Message::Notify(event) => {
+      let sound = sound::play(app, event);
+
+      if event == TimerEvent::Warning || app.config.notification.escalation.is_none() {
+        return Task::batch([sound, notify::notify(app, event, None)]);
+      }
+
+      app.escalation = Some(Escalating { event, sent: 0 });
+      return Task::batch([sound, notify::notify(app, event, Some(0))]);
+    }
+    Message::MqttEvent(e) => match e {
+      MqttEvent::Connected(client) => {
+        app.mqtt = Some(client);
+        return mqtt::publish(app, None);
+      }
+      MqttEvent::Command(command) => {
+        return Task::done(match command {
+          Command::Pause => Message::Pause(true),
+          Command::Resume => Message::Pause(false),
+          Command::Skip => Message::Skip,
+          Command::Snooze => Message::Snooze,
+        })
+      }
+      MqttEvent::Error(e) => eprintln!("mqtt: {e}"),
+    },
+    Message::HookFinished(result)
+    | Message::WebhookFinished(result)
+    | Message::MqttPublished(result)
+    | Message::Synced(result)
+    | Message::Played(result) => {
+      if let Err(e) = result {
+        eprintln!("{e}");
+        return info::send(e);
+      }
+    }
+    Message::FlushWebhooks => return webhook::flush(app),
+    Message::Recorded(result) => match result {
+      Ok(session) => {
+        if let Page::Stats = app.page {
+          stats::refresh(app);
+        }
+        // the time trackers are read from the history, so they wait for the write
+        if session.phase == Phase::Work && session.outcome == Outcome::Completed {
+          return export::sync_configured(app);
+        }
+      }
+      Err(e) => {
+        eprintln!("{e}");
+        return info::send(e);
+      }
+    },
+    Message::Escalate => {
+      let (Some(escalating), Some(escalation)) = (&mut app.escalation, &app.config.notification.escalation) else {
+        return Task::none();
+      };
+
+      escalating.sent += 1;
+      if escalating.sent > escalation.max {
+        app.escalation = None;
+        app.timer.settle();
+        return Task::none();
+      }
+
+      let (event, sent) = (escalating.event, escalating.sent);
+      return notify::notify(app, event, Some(sent));
+    }
+    Message::Acknowledge => {
+      app.escalation = None;
+      app.timer.settle();
+    }
+    Message::Notified(report) => {
+      if report.banner.is_some() {
+        app.banner = report.banner;
+      }
+      if !report.errors.is_empty() {
+        eprintln!("{}", report.errors.join("\n"));
+        return info::send(report.errors.join("; "));
+      }
+    }
+    Message::NotificationAction(action) => {
+      // the snooze still needs to know the notification was pending
+      if action != Action::Snooze {
+        app.escalation = None;
+      }
+      match action {
+        Action::Snooze => return Task::done(Message::Snooze),
+        Action::Skip => return Task::done(Message::Skip),
+        Action::Next => {
+          app.timer.restart_phase();
+          let recorded = history::transition(app, Outcome::Interrupted);
+          state::record(app, JournalEvent::Phase);
+          return recorded;
+        }
+      }
+    }
+    Message::DismissBanner => {
+      app.banner = None;
+      app.escalation = None;
+      app.timer.settle();
+    }
     #[cfg(debug_assertions)]
     Message::ChangeDebugMode(v) => app.debug_mode = v,
   }
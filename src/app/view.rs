@@ -1,6 +1,9 @@
 mod config;
 mod main;
+mod overlay;
 mod root;
+mod stats;
+mod tasks;
 
 pub use root::view;
 
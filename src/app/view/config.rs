@@ -1,6 +1,6 @@
 use crate::app::{
   config::{ChangeConfig, ConfigEvent},
-  timer::ticker::Ticker,
+  timer::{ticker::Ticker, TimerEvent},
 };
 
 use super::*;
@@ -19,7 +19,7 @@ pub(super) fn view(app: &App) -> Element<Message> {
   let mut config_items = vec![
     ("select mode", mode_pick.into()),
     ("select theme", theme_pick.into()),
-    ("testing notify", config_button("Send").on_press(Message::Notify).into()),
+    ("testing notify", config_button("Send").on_press(Message::Notify(TimerEvent::WorkEnd)).into()),
   ];
 
   #[cfg(debug_assertions)]
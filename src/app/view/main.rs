@@ -1,4 +1,5 @@
 use super::*;
+use crate::app::{counter, task::TaskMessage, timer::ticker::Phase};
 
 pub(super) fn view(app: &App) -> Element<Message> {
   let next = match app.timer.next {
@@ -6,14 +7,88 @@ pub(super) fn view(app: &App) -> Element<Message> {
     None => "Break".to_string(),
   };
 
+  let remaining = app.timer.remaining().map(|v| {
+    let secs = v.as_secs();
+    text(format!("{:02}:{:02}", secs / 60, secs % 60)).size(32)
+  });
+
   let pause = if app.timer.enable { "Pause" } else { "Start" };
 
+  let resume = app.resume.as_ref().map(|saved| {
+    Row::new()
+      .push(text(format!("Resume previous {} timer?", saved.ticker.name)))
+      .push(button("Resume").on_press(Message::Resume(true)))
+      .push(button("Discard").on_press(Message::Resume(false)))
+      .align_y(Center)
+      .spacing(8)
+  });
+
+  let activity = app
+    .activity
+    .as_ref()
+    .filter(|_| app.timer.phase() == Phase::Break)
+    .map(|v| {
+      Row::new()
+        .push(text(&v.title))
+        .push(button("Done").on_press(Message::ActivityDone))
+        .align_y(Center)
+        .spacing(8)
+    });
+
+  let counters = counter::today(app).into_iter().fold(Row::new().spacing(8), |row, (name, count, target)| {
+    let count = match target {
+      Some(target) => format!("+1 {name} ({count}/{target})"),
+      None => format!("+1 {name} ({count})"),
+    };
+    row.push(button(text(count)).on_press(Message::Count(name.to_owned())))
+  });
+
+  let choices: Vec<_> = std::iter::once(Choice(None, "no task".to_owned()))
+    .chain(
+      app
+        .tasks
+        .task
+        .iter()
+        .filter(|v| !v.done)
+        .map(|v| Choice(Some(v.id), format!("{} ({}/{})", v.title, v.actual, v.estimate))),
+    )
+    .collect();
+  let selected = choices.iter().find(|v| v.0 == app.tasks.active).cloned();
+  let task = pick_list(choices, selected, |v| Message::Task(TaskMessage::Activate(v.0))).text_size(12);
+
   Element::from({
     Column::new()
+      .push_maybe(resume)
+      .push_maybe(remaining)
       .push(text(next).size(20))
-      .push(button(pause).on_press(Message::Pause(app.timer.enable)))
+      .push(task)
+      .push(
+        Row::new()
+          .push(button(pause).on_press(Message::Pause(app.timer.enable)))
+          .push(button("Snooze").on_press_maybe(app.timer.enable.then_some(Message::Snooze)))
+          .spacing(8),
+      )
+      .push_maybe(activity)
+      .push_maybe((!app.counters.is_empty()).then_some(counters))
+      .push_maybe(
+        app
+          .escalation
+          .is_some()
+          .then(|| button("Acknowledge").on_press(Message::Acknowledge)),
+      )
+      .push_maybe((app.timer.snoozes > 0).then(|| text(format!("snoozed {} times", app.timer.snoozes)).size(12)))
       .width(Fill)
       .align_x(Center)
       .spacing(12)
   })
 }
+
+/// Entry of the active task picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Choice(Option<u32>, String);
+
+impl std::fmt::Display for Choice {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.1)
+  }
+}
@@ -0,0 +1,43 @@
+use iced::{Color, Theme};
+
+use super::*;
+use crate::app::overlay::postpones_left;
+
+pub(super) fn view(app: &App) -> Element<Message> {
+  let Some(config) = &app.config.overlay else {
+    return Element::from(Space::new(Fill, Fill));
+  };
+
+  let remaining = app.timer.remaining().unwrap_or_default().as_secs();
+  let activity = app.activity.as_ref().map(|v| {
+    Row::new()
+      .push(text(&v.title).size(24))
+      .push(button("Done").on_press(Message::ActivityDone))
+      .align_y(Center)
+      .spacing(16)
+  });
+
+  let left = postpones_left(app);
+  let postpone = button(text(format!("Postpone {} min ({left} left)", config.postpone.as_minutes())))
+    .on_press_maybe((left > 0).then_some(Message::Postpone));
+
+  let skip = (!config.strict).then(|| button("Skip break").on_press(Message::Skip));
+
+  Element::from(
+    container(
+      Column::new()
+        .push(text("Break").size(40))
+        .push(text(format!("{:02}:{:02}", remaining / 60, remaining % 60)).size(96))
+        .push_maybe(activity)
+        .push(Row::new().push(postpone).push_maybe(skip).spacing(16))
+        .align_x(Center)
+        .spacing(24),
+    )
+    .center(Fill)
+    .style(|_: &Theme| container::Style {
+      background: Some(Color::from_rgba(0.0, 0.0, 0.0, 0.85).into()),
+      text_color: Some(Color::WHITE),
+      ..Default::default()
+    }),
+  )
+}
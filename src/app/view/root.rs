@@ -2,9 +2,27 @@ use iced::Color;
 
 use super::*;
 
-pub fn view(app: &App, _id: window::Id) -> Element<Message> {
+pub fn view(app: &App, id: window::Id) -> Element<Message> {
+  if app.overlay == Some(id) {
+    return overlay::view(app);
+  }
+
   let info = app.info.as_ref().map(text);
 
+  let banner = app.banner.as_ref().map(|banner| {
+    container(
+      Row::new()
+        .push(text(banner))
+        .push(Space::with_width(Fill))
+        .push(button("OK").on_press(Message::DismissBanner))
+        .align_y(Center)
+        .spacing(8),
+    )
+    .style(container::rounded_box)
+    .padding(8)
+    .width(Fill)
+  });
+
   let view = Element::from({
     container(
       Column::new()
@@ -12,12 +30,38 @@ pub fn view(app: &App, _id: window::Id) -> Element<Message> {
           Row::new()
             .push_maybe(info)
             .push(Space::with_width(Fill))
+            .push(tooltip(
+              button(text("T").align_x(Center))
+                .on_press_with(|| {
+                  Message::ChangePage(match app.page {
+                    Page::Tasks => Page::Main,
+                    _ => Page::Tasks,
+                  })
+                })
+                .height(32)
+                .width(32),
+              "Tasks",
+              tooltip::Position::Bottom,
+            ))
+            .push(tooltip(
+              button(text("%").align_x(Center))
+                .on_press_with(|| {
+                  Message::ChangePage(match app.page {
+                    Page::Stats => Page::Main,
+                    _ => Page::Stats,
+                  })
+                })
+                .height(32)
+                .width(32),
+              "Stats",
+              tooltip::Position::Bottom,
+            ))
             .push(
               button(text("!").align_x(Center))
                 .on_press_with(|| {
                   Message::ChangePage(match app.page {
-                    Page::Main => Page::Config,
                     Page::Config => Page::Main,
+                    _ => Page::Config,
                   })
                 })
                 .height(32)
@@ -25,10 +69,13 @@ pub fn view(app: &App, _id: window::Id) -> Element<Message> {
             )
             .spacing(4),
         )
+        .push_maybe(banner)
         .push(
           container(match app.page {
             Page::Main => main::view(app),
             Page::Config => config::view(app),
+            Page::Stats => stats::view(app),
+            Page::Tasks => tasks::view(app),
           })
           .center_y(Fill),
         )
@@ -0,0 +1,119 @@
+use iced::{
+  alignment, mouse,
+  widget::canvas::{self, Frame, Geometry, Text as CanvasText},
+  Pixels, Point, Rectangle, Renderer, Size,
+};
+
+use crate::app::{
+  export::Format,
+  stats::{Bar, Range, StatsMessage},
+};
+
+use super::*;
+
+pub(super) fn view(app: &App) -> Element<Message> {
+  let page = &app.stats;
+  let stats = &page.stats;
+
+  let range = pick_list(Range::ALL, Some(page.range), |v| Message::Stats(StatsMessage::Range(v))).text_size(12);
+  let filter = pick_list(page.filters.as_slice(), Some(page.filter.clone()), |v| {
+    Message::Stats(StatsMessage::Filter(v))
+  })
+  .text_size(12);
+
+  let adherence = match stats.adherence {
+    Some(v) => format!("{:.0}%", v * 100.0),
+    None => "-".to_owned(),
+  };
+
+  let totals = [
+    ("focus", format!("{} min", stats.focus.as_secs() / 60)),
+    ("pomodoros", stats.pomodoros.to_string()),
+    ("breaks taken", adherence),
+    ("streak", format!("{} days (best {})", stats.streak, stats.best_streak)),
+  ]
+  .into_iter()
+  .fold(Row::new().spacing(16), |row, (label, value)| {
+    row.push(Column::new().push(text(label).size(12)).push(text(value)).align_x(Center))
+  });
+
+  Element::from({
+    Column::new()
+      .push(
+        Row::new()
+          .push(text("Stats"))
+          .push(Space::with_width(Fill))
+          .push(range)
+          .push(filter)
+          .align_y(Center)
+          .spacing(4),
+      )
+      .push(canvas(BarChart { bars: &stats.bars }).width(Fill).height(Fill))
+      .push(totals)
+      .push(
+        [Format::Csv, Format::Json, Format::Ics]
+          .into_iter()
+          .fold(Row::new().push(text("export").size(12)).spacing(4), |row, format| {
+            row.push(button(text(format.extension()).size(12)).on_press(Message::Export(format)))
+          })
+          .align_y(Center),
+      )
+      .width(Fill)
+      .align_x(Center)
+      .spacing(12)
+  })
+}
+
+/// Focus minutes as vertical bars, labelled below.
+struct BarChart<'a> {
+  bars: &'a [Bar],
+}
+
+impl canvas::Program<Message> for BarChart<'_> {
+  type State = ();
+
+  fn draw(
+    &self,
+    _state: &Self::State,
+    renderer: &Renderer,
+    theme: &Theme,
+    bounds: Rectangle,
+    _cursor: mouse::Cursor,
+  ) -> Vec<Geometry> {
+    const LABEL: f32 = 14.0;
+
+    let mut frame = Frame::new(renderer, bounds.size());
+    let palette = theme.extended_palette();
+
+    let max = self.bars.iter().map(|v| v.minutes).fold(0.0, f32::max).max(1.0);
+    let width = bounds.width / self.bars.len().max(1) as f32;
+    let height = bounds.height - LABEL;
+    // label only a handful of bars when there are many
+    let step = (self.bars.len() / 8).max(1);
+
+    for (i, bar) in self.bars.iter().enumerate() {
+      let x = i as f32 * width;
+      let h = height * bar.minutes / max;
+
+      frame.fill_rectangle(
+        Point::new(x + width * 0.1, height - h),
+        Size::new(width * 0.8, h),
+        palette.primary.base.color,
+      );
+
+      if i % step == 0 {
+        frame.fill_text(CanvasText {
+          content: bar.label.clone(),
+          position: Point::new(x + width / 2.0, bounds.height),
+          color: palette.background.base.text,
+          size: Pixels(10.0),
+          horizontal_alignment: alignment::Horizontal::Center,
+          vertical_alignment: alignment::Vertical::Bottom,
+          ..Default::default()
+        });
+      }
+    }
+
+    vec![frame.into_geometry()]
+  }
+}
@@ -0,0 +1,60 @@
+use crate::app::task::TaskMessage;
+
+use super::*;
+
+pub(super) fn view(app: &App) -> Element<Message> {
+  let form = &app.task_form;
+
+  let add = Row::new()
+    .push(
+      text_input("title", &form.title)
+        .on_input(|v| Message::Task(TaskMessage::Title(v)))
+        .on_submit(Message::Task(TaskMessage::Add)),
+    )
+    .push(
+      text_input("tags", &form.tags)
+        .on_input(|v| Message::Task(TaskMessage::Tags(v)))
+        .on_submit(Message::Task(TaskMessage::Add))
+        .width(96),
+    )
+    .push(
+      text_input("est.", &form.estimate)
+        .on_input(|v| Message::Task(TaskMessage::Estimate(v)))
+        .on_submit(Message::Task(TaskMessage::Add))
+        .width(48),
+    )
+    .push(button("Add").on_press(Message::Task(TaskMessage::Add)))
+    .spacing(4);
+
+  let tasks = app.tasks.task.iter().fold(Column::new().spacing(4), |column, task| {
+    let active = app.tasks.active == Some(task.id);
+    let tags = task.tags.iter().map(|v| format!("#{v}")).collect::<Vec<_>>().join(" ");
+
+    column.push(
+      Row::new()
+        .push(checkbox("", task.done).on_toggle(|v| Message::Task(TaskMessage::Done(task.id, v))))
+        .push(text(&task.title))
+        .push(text(tags).size(12))
+        .push(Space::with_width(Fill))
+        .push(text(format!("{}/{}", task.actual, task.estimate)))
+        .push(
+          button(if active { "Stop" } else { "Start" }).on_press_maybe(
+            (!task.done).then_some(Message::Task(TaskMessage::Activate((!active).then_some(task.id)))),
+          ),
+        )
+        .push(button("x").on_press(Message::Task(TaskMessage::Remove(task.id))))
+        .align_y(Center)
+        .spacing(8),
+    )
+  });
+
+  Element::from({
+    Column::new()
+      .push(text("Tasks").center())
+      .push(add)
+      .push(scrollable(tasks).height(Fill))
+      .width(Fill)
+      .align_x(Center)
+      .spacing(12)
+  })
+}
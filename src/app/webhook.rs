@@ -0,0 +1,431 @@
+use std::{
+  collections::HashSet,
+  fs::{self, OpenOptions},
+  io::Write,
+  path::PathBuf,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use iced::{futures::future, Task};
+use serde::{Deserialize, Serialize};
+
+use super::{config::WebhookConfig, event::Event, template, App, Message};
+
+/// How often the outbox is retried.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// A rendered POST, kept in the outbox until it went through.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Request {
+  pub url: String,
+  pub headers: Vec<(String, String)>,
+  pub body: String,
+  pub retries: u32,
+  pub backoff: Duration,
+}
+
+impl Request {
+  pub fn new(config: &WebhookConfig, event: &Event) -> Self {
+    let body = match &config.body {
+      // `{json}` already is JSON, everything else ends up inside a string
+      Some(body) => template::render_with(body, |name| match name {
+        "json" => event.get(name),
+        _ => event.get(name).map(|v| escape_json(&v)),
+      }),
+      None => serde_json::to_string(event).unwrap_or_default(),
+    };
+
+    let mut headers: Vec<_> = config.headers.clone().into_iter().collect();
+    if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("content-type")) {
+      headers.push(("Content-Type".to_owned(), "application/json".to_owned()));
+    }
+
+    Self {
+      url: config.url.clone(),
+      headers,
+      body,
+      retries: config.retries,
+      backoff: (&config.backoff).into(),
+    }
+  }
+
+  /// Copy without credentials, fit to be written to disk.
+  fn without_secrets(&self) -> Self {
+    Self {
+      headers: self.headers.iter().filter(|(k, _)| !secret(k)).cloned().collect(),
+      ..self.clone()
+    }
+  }
+
+  /// Puts back the credentials of the webhook posting to the same url.
+  fn with_secrets(&self, webhooks: &[WebhookConfig]) -> Self {
+    let mut request = self.clone();
+    let secrets = webhooks
+      .iter()
+      .filter(|v| v.url == self.url)
+      .flat_map(|v| v.headers.iter())
+      .filter(|(k, _)| secret(k));
+    for (k, v) in secrets {
+      if !request.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case(k)) {
+        request.headers.push((k.clone(), v.clone()));
+      }
+    }
+    request
+  }
+}
+
+fn secret(header: &str) -> bool {
+  let header = header.to_ascii_lowercase();
+  matches!(header.as_str(), "authorization" | "proxy-authorization" | "cookie")
+    || ["token", "key", "secret"].iter().any(|v| header.contains(v))
+}
+
+/// `value` escaped to go between the quotes of a JSON string.
+fn escape_json(value: &str) -> String {
+  let quoted = serde_json::to_string(value).unwrap_or_default();
+  quoted[1..quoted.len() - 1].to_owned()
+}
+
+enum Failure {
+  // worth trying again later
+  Transient(String),
+  Permanent(String),
+}
+
+/// Requests that could not be delivered, one JSON line each, without their credentials.
+pub struct Outbox {
+  path: PathBuf,
+  lock: Mutex<()>,
+  // held while the outbox is being posted, so entries are not sent twice
+  flushing: tokio::sync::Mutex<()>,
+}
+
+impl Outbox {
+  pub fn new(path: PathBuf) -> Arc<Self> {
+    Arc::new(Self {
+      path,
+      lock: Mutex::new(()),
+      flushing: tokio::sync::Mutex::new(()),
+    })
+  }
+
+  pub fn push(&self, request: &Request) -> Result<(), String> {
+    let _lock = self.lock.lock().map_err(|e| e.to_string())?;
+
+    let mut line = serde_json::to_vec(&request.without_secrets()).map_err(|e| e.to_string())?;
+    line.push(b'\n');
+
+    OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&self.path)
+      .and_then(|mut file| file.write_all(&line))
+      .map_err(|e| format!("{}: {e}", self.path.display()))
+  }
+
+  /// Every pending request, skipping lines that no longer parse.
+  pub fn pending(&self) -> Vec<Request> {
+    let Ok(_lock) = self.lock.lock() else {
+      return Vec::new();
+    };
+
+    fs::read_to_string(&self.path)
+      .unwrap_or_default()
+      .lines()
+      .filter_map(|v| serde_json::from_str(v).ok())
+      .collect()
+  }
+
+  /// Drops `done` from the outbox, along with lines that no longer parse.
+  pub fn remove(&self, done: &[Request]) -> Result<(), String> {
+    let _lock = self.lock.lock().map_err(|e| e.to_string())?;
+    let error = |e: std::io::Error| format!("{}: {e}", self.path.display());
+
+    let content = match fs::read_to_string(&self.path) {
+      Ok(v) => v,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+      Err(e) => return Err(error(e)),
+    };
+
+    let mut done = done.to_vec();
+    let mut kept = String::new();
+    for line in content.lines() {
+      let Ok(request) = serde_json::from_str::<Request>(line) else {
+        continue;
+      };
+      match done.iter().position(|v| *v == request) {
+        Some(i) => {
+          done.swap_remove(i);
+        }
+        None => {
+          kept.push_str(line);
+          kept.push('\n');
+        }
+      }
+    }
+
+    if kept.is_empty() {
+      return fs::remove_file(&self.path).map_err(error);
+    }
+    // a crash while writing must not lose what is still queued
+    let temp = self.path.with_extension("tmp");
+    fs::write(&temp, kept).and_then(|_| fs::rename(&temp, &self.path)).map_err(error)
+  }
+}
+
+pub(crate) fn send(app: &App, event: &Event) -> Task<Message> {
+  let requests: Vec<_> = app
+    .config
+    .webhooks
+    .iter()
+    .filter(|v| v.events.is_empty() || v.events.contains(&event.event))
+    .map(|v| Request::new(v, event))
+    .collect();
+
+  if requests.is_empty() {
+    return Task::none();
+  }
+
+  Task::perform(deliver(app.outbox.clone(), requests), Message::WebhookFinished)
+}
+
+/// Retries whatever is left in the outbox.
+pub(crate) fn flush(app: &App) -> Task<Message> {
+  Task::perform(
+    flush_outbox(app.outbox.clone(), app.config.webhooks.clone()),
+    Message::WebhookFinished,
+  )
+}
+
+/// Posts `requests`, queueing those that failed for a transient reason.
+pub async fn deliver(outbox: Arc<Outbox>, requests: Vec<Request>) -> Result<(), String> {
+  let results = future::join_all(requests.into_iter().map(|request| async move {
+    let result = post(&request).await;
+    (request, result)
+  }))
+  .await;
+
+  let mut errors = Vec::new();
+  for (request, result) in results {
+    match result {
+      Ok(()) => (),
+      Err(Failure::Transient(e)) => {
+        if let Err(e) = outbox.push(&request) {
+          errors.push(e);
+        }
+        errors.push(format!("{}: {e}, queued", request.url));
+      }
+      Err(Failure::Permanent(e)) => errors.push(format!("{}: {e}", request.url)),
+    }
+  }
+
+  match errors.is_empty() {
+    true => Ok(()),
+    false => Err(errors.join("; ")),
+  }
+}
+
+/// Posts the outbox in order, once each, and removes what went through or was rejected for good.
+///
+/// A url that is still unreachable is not tried again until the next flush.
+pub async fn flush_outbox(outbox: Arc<Outbox>, webhooks: Vec<WebhookConfig>) -> Result<(), String> {
+  let Ok(_flushing) = outbox.flushing.try_lock() else {
+    return Ok(());
+  };
+
+  let mut done = Vec::new();
+  let mut down = HashSet::new();
+  let mut errors = Vec::new();
+  for request in outbox.pending() {
+    if down.contains(&request.url) {
+      continue;
+    }
+    match post_once(&request.with_secrets(&webhooks)).await {
+      Ok(()) => done.push(request),
+      Err(Failure::Transient(_)) => {
+        down.insert(request.url.clone());
+      }
+      Err(Failure::Permanent(e)) => {
+        errors.push(format!("{}: {e}, dropped", request.url));
+        done.push(request);
+      }
+    }
+  }
+
+  if let Err(e) = outbox.remove(&done) {
+    errors.push(e);
+  }
+
+  match errors.is_empty() {
+    true => Ok(()),
+    false => Err(errors.join("; ")),
+  }
+}
+
+async fn post(request: &Request) -> Result<(), Failure> {
+  let mut delay = request.backoff;
+
+  for attempt in 0..=request.retries {
+    match post_once(request).await {
+      Err(Failure::Transient(_)) if attempt < request.retries => {
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+      }
+      result => return result,
+    }
+  }
+
+  unreachable!()
+}
+
+async fn post_once(request: &Request) -> Result<(), Failure> {
+  let request = request.clone();
+  tokio::task::spawn_blocking(move || post_blocking(&request))
+    .await
+    .map_err(|e| Failure::Permanent(e.to_string()))?
+}
+
+fn post_blocking(request: &Request) -> Result<(), Failure> {
+  let mut req = ureq::post(&request.url).timeout(Duration::from_secs(10));
+  for (k, v) in &request.headers {
+    req = req.set(k, v);
+  }
+
+  match req.send_string(&request.body) {
+    Ok(response) if (200..300).contains(&response.status()) => Ok(()),
+    Ok(response) => Err(Failure::Permanent(format!("status {}", response.status()))),
+    Err(ureq::Error::Status(code, _)) if (500..600).contains(&code) || code == 429 => {
+      Err(Failure::Transient(format!("status {code}")))
+    }
+    Err(ureq::Error::Status(code, _)) => Err(Failure::Permanent(format!("status {code}"))),
+    Err(e) => Err(Failure::Transient(e.to_string())),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    io::{BufRead, BufReader, Read},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+  };
+
+  use super::*;
+  use crate::util::test::{block_on, temp_path};
+
+  /// Local HTTP server answering with `statuses` in turn and sending back each request body.
+  fn server(statuses: Vec<u16>) -> (String, mpsc::Receiver<(String, String)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+      for (status, stream) in statuses.into_iter().zip(listener.incoming()) {
+        let mut stream = BufReader::new(stream.unwrap());
+
+        let mut headers = String::new();
+        let mut len = 0;
+        loop {
+          let mut line = String::new();
+          stream.read_line(&mut line).unwrap();
+          if line == "\r\n" {
+            break;
+          }
+          if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            len = v.trim().parse().unwrap();
+          }
+          headers.push_str(&line);
+        }
+        let mut body = vec![0; len];
+        stream.read_exact(&mut body).unwrap();
+
+        write!(stream.get_mut(), "HTTP/1.1 {status} X\r\nContent-Length: 0\r\n\r\n").unwrap();
+        tx.send((headers, String::from_utf8(body).unwrap())).unwrap();
+      }
+    });
+
+    (url, rx)
+  }
+
+  fn request(url: &str, retries: u32) -> Request {
+    Request {
+      url: url.to_owned(),
+      headers: vec![("X-Timer".to_owned(), "test".to_owned())],
+      body: "{\"event\":\"work_end\"}".to_owned(),
+      retries,
+      backoff: Duration::from_millis(10),
+    }
+  }
+
+  fn outbox(name: &str) -> Arc<Outbox> {
+    Outbox::new(temp_path(&format!("outbox-{name}")))
+  }
+
+  #[test]
+  fn retried_until_delivered() {
+    let (url, rx) = server(vec![503, 200]);
+    let outbox = outbox("retry");
+
+    block_on(deliver(outbox.clone(), vec![request(&url, 2)])).unwrap();
+
+    let (headers, body) = rx.recv().unwrap();
+    assert!(headers.to_ascii_lowercase().contains("x-timer: test"));
+    assert_eq!(body, "{\"event\":\"work_end\"}");
+    assert_eq!(rx.recv().unwrap().1, body);
+    assert!(outbox.pending().is_empty());
+  }
+
+  #[test]
+  fn queued_while_offline() {
+    // nothing listens on a port that was just released
+    let url = {
+      let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+      format!("http://{}/hook", listener.local_addr().unwrap())
+    };
+    let outbox = outbox("offline");
+
+    let mut offline = request(&url, 0);
+    offline.headers.push(("Authorization".to_owned(), "Bearer hunter2".to_owned()));
+    assert!(block_on(deliver(outbox.clone(), vec![offline])).is_err());
+    assert!(!fs::read_to_string(&outbox.path).unwrap().contains("hunter2"));
+
+    // still offline, so it stays queued
+    block_on(flush_outbox(outbox.clone(), Vec::new())).unwrap();
+    assert_eq!(outbox.pending(), vec![request(&url, 0)]);
+
+    let (url, rx) = server(vec![200]);
+    let mut queued = outbox.pending().remove(0);
+    queued.url = url.clone();
+    outbox.remove(&outbox.pending()).unwrap();
+    outbox.push(&queued).unwrap();
+
+    let webhooks = vec![WebhookConfig {
+      url,
+      headers: [("Authorization".to_owned(), "Bearer hunter2".to_owned())].into(),
+      ..Default::default()
+    }];
+    block_on(flush_outbox(outbox.clone(), webhooks)).unwrap();
+    let (headers, body) = rx.recv().unwrap();
+    assert!(headers.to_ascii_lowercase().contains("authorization: bearer hunter2"));
+    assert_eq!(body, queued.body);
+    assert!(outbox.pending().is_empty());
+  }
+
+  #[test]
+  fn body_template_is_json() {
+    use crate::app::{event::EventKind, timer::Timer};
+
+    let mut event = Event::new(EventKind::WorkEnd, &Timer::default());
+    event.ticker = "say \"hi\"".to_owned();
+    let config = WebhookConfig {
+      body: Some(r#"{"text": "{ticker}", "event": {json}}"#.to_owned()),
+      ..Default::default()
+    };
+
+    let body: serde_json::Value = serde_json::from_str(&Request::new(&config, &event).body).unwrap();
+    assert_eq!(body["text"], "say \"hi\"");
+    assert_eq!(body["event"]["event"], "work_end");
+  }
+}
@@ -5,8 +5,14 @@ mod app;
 mod subscription;
 mod util;
 
-use app::App;
-use clap::Parser;
+use std::path::PathBuf;
+
+use app::{
+  export::{Format, Target},
+  App,
+};
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand};
 
 pub(crate) const APPID: &str = "io.github.monax-owo.timer";
 #[allow(unused)]
@@ -23,10 +29,41 @@ struct Args {
   register: bool,
   #[arg(short, long)]
   un_register: bool,
+
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Write the session history to a file or stdout
+  Export {
+    #[arg(short, long, value_enum, default_value = "csv")]
+    format: Format,
+    /// First day, defaults to today
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// Last day, included, defaults to today
+    #[arg(long)]
+    to: Option<NaiveDate>,
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+  },
+  /// Add completed work phases to Timewarrior or an org file, skipping ones already there
+  Sync {
+    #[arg(value_enum)]
+    target: Target,
+    /// First day, defaults to today
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// Last day, included, defaults to today
+    #[arg(long)]
+    to: Option<NaiveDate>,
+  },
 }
 
 fn main() -> iced::Result {
-  let args = Args::parse();
+  let mut args = Args::parse();
 
   #[cfg(debug_assertions)]
   if args.gen_icons {
@@ -44,6 +81,24 @@ fn main() -> iced::Result {
     println!("successfully generate icons");
   }
 
+  if let Some(command) = args.command.take() {
+    let today = Local::now().date_naive();
+    let result = match command {
+      Command::Export {
+        format,
+        from,
+        to,
+        output,
+      } => app::export::run(format, from.unwrap_or(today), to.unwrap_or(today), output),
+      Command::Sync { target, from, to } => app::export::run_sync(target, from.unwrap_or(today), to.unwrap_or(today)),
+    };
+    if let Err(e) = result {
+      eprintln!("{e}");
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
   init(args);
 
   iced::daemon(APP_NAME, App::update, App::view)
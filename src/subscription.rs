@@ -1,9 +1,17 @@
-use std::time::Duration;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::{mpsc as std_mpsc, Mutex},
+  thread,
+  time::{Duration, SystemTime},
+};
 
+use chrono::{Local, NaiveDateTime};
 use iced::{
-  futures::{SinkExt, Stream},
+  futures::{self, SinkExt, Stream},
   stream, Subscription,
 };
+use tokio::sync::mpsc;
 use tray_icon::{
   menu::{MenuEvent, MenuId},
   TrayIconEvent,
@@ -21,39 +29,177 @@ pub fn tray_listener() -> Subscription<TrayEvent> {
 
 fn menu_listener() -> impl Stream<Item = TrayEvent> {
   const CONNECTION: usize = 2;
-  const SLEEP_MS: u64 = 500;
 
   stream::channel(CONNECTION, |mut output| async move {
-    let menu_event_receiver = MenuEvent::receiver();
+    let receiver = MenuEvent::receiver();
+    let mut events = forward(move || receiver.recv().ok());
 
-    loop {
-      if let Ok(MenuEvent { id }) = menu_event_receiver.recv() {
-        output.send(TrayEvent::MenuEvent(id)).await.unwrap();
+    while let Some(MenuEvent { id }) = events.recv().await {
+      if output.send(TrayEvent::MenuEvent(id)).await.is_err() {
+        break;
       }
-
-      tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await;
     }
   })
 }
 
 fn icon_listener() -> impl Stream<Item = TrayEvent> {
   const CONNECTION: usize = 8;
-  const SLEEP_MS: u64 = 100;
 
   stream::channel(CONNECTION, |mut output| async move {
-    let icon_event_receiver = TrayIconEvent::receiver();
+    let receiver = TrayIconEvent::receiver();
+    let mut events = forward(move || receiver.recv().ok());
 
-    loop {
-      if let Ok(e) = icon_event_receiver.recv_timeout(Duration::from_millis(50)) {
-        match e {
-          TrayIconEvent::Move { .. } => {
-            continue;
-          }
-          _ => output.send(TrayEvent::IconEvent(e)).await.unwrap(),
+    while let Some(e) = events.recv().await {
+      let Some(e) = icon_event(e) else {
+        continue;
+      };
+      if output.send(e).await.is_err() {
+        break;
+      }
+    }
+  })
+}
+
+fn icon_event(e: TrayIconEvent) -> Option<TrayEvent> {
+  match e {
+    TrayIconEvent::Move { .. } => None,
+    _ => Some(TrayEvent::IconEvent(e)),
+  }
+}
+
+/// Actions invoked on notifications, see [`crate::app::notify::action`].
+pub fn action_listener<T: Send + 'static>(
+  receiver: &'static Mutex<std_mpsc::Receiver<T>>,
+) -> impl Stream<Item = T> {
+  const CONNECTION: usize = 2;
+
+  stream::channel(CONNECTION, move |mut output| async move {
+    let mut events = forward(move || receiver.lock().ok()?.recv().ok());
+
+    while let Some(e) = events.recv().await {
+      if output.send(e).await.is_err() {
+        break;
+      }
+    }
+  })
+}
+
+/// Runs the blocking `recv` on its own thread and forwards whatever it yields into an async channel.
+///
+/// The thread stops once `recv` returns `None` or the returned receiver is dropped.
+fn forward<T, F>(mut recv: F) -> mpsc::UnboundedReceiver<T>
+where
+  T: Send + 'static,
+  F: FnMut() -> Option<T> + Send + 'static,
+{
+  let (tx, rx) = mpsc::unbounded_channel();
+
+  thread::spawn(move || {
+    while let Some(v) = recv() {
+      if tx.send(v).is_err() {
+        break;
+      }
+    }
+  });
+
+  rx
+}
+
+/// Fires once `deadline` has passed, then every second until the subscription is replaced.
+pub fn deadline(deadline: NaiveDateTime) -> Subscription<()> {
+  Subscription::run_with_id(deadline, wait_until(deadline))
+}
+
+pub(crate) fn wait_until(deadline: NaiveDateTime) -> impl Stream<Item = ()> {
+  // tickers compare with `>`, so wake up just after the deadline
+  const MARGIN: Duration = Duration::from_millis(10);
+  const RETRY: Duration = Duration::from_secs(1);
+  // the monotonic clock stops while the system is suspended, so the wall clock is checked again this often
+  const RECHECK: Duration = Duration::from_secs(30);
+
+  futures::stream::unfold(Some(deadline), |deadline| async move {
+    match deadline {
+      Some(deadline) => {
+        while let Ok(left) = (deadline - Local::now().naive_local()).to_std() {
+          tokio::time::sleep((left + MARGIN).min(RECHECK)).await;
         }
       }
+      None => tokio::time::sleep(RETRY).await,
+    }
+    Some(((), None))
+  })
+}
+
+/// Yields `path` whenever its modification time changes, checked every couple of seconds.
+pub fn file_changes(path: PathBuf) -> Subscription<PathBuf> {
+  Subscription::run_with_id(path.clone(), watch(path))
+}
+
+fn watch(path: PathBuf) -> impl Stream<Item = PathBuf> {
+  const INTERVAL: Duration = Duration::from_secs(2);
+
+  fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|v| v.modified()).ok()
+  }
+
+  let last = modified(&path);
+  futures::stream::unfold((path, last), |(path, last)| async move {
+    loop {
+      tokio::time::sleep(INTERVAL).await;
 
-      tokio::time::sleep(Duration::from_millis(SLEEP_MS)).await;
+      let current = modified(&path);
+      if current != last {
+        return Some((path.clone(), (path, current)));
+      }
     }
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use tray_icon::{menu::MenuId, Rect, TrayIconId};
+
+  use super::*;
+
+  #[test]
+  fn forward_synthetic_events() {
+    let (tx, rx) = std_mpsc::channel();
+    let mut events = forward(move || rx.recv().ok());
+
+    tx.send(MenuEvent { id: MenuId::new("show") }).unwrap();
+    tx.send(MenuEvent { id: MenuId::new("quit") }).unwrap();
+
+    assert_eq!(events.blocking_recv().map(|e| e.id), Some(MenuId::new("show")));
+    assert_eq!(events.blocking_recv().map(|e| e.id), Some(MenuId::new("quit")));
+
+    // closing the source ends the stream
+    drop(tx);
+    assert!(events.blocking_recv().is_none());
+  }
+
+  #[test]
+  fn icon_listener_skips_move() {
+    let (tx, rx) = std_mpsc::channel();
+    let mut events = forward(move || rx.recv().ok());
+
+    tx.send(TrayIconEvent::Move {
+      id: TrayIconId::new("tray"),
+      position: Default::default(),
+      rect: Rect::default(),
+    })
+    .unwrap();
+    tx.send(TrayIconEvent::Enter {
+      id: TrayIconId::new("tray"),
+      position: Default::default(),
+      rect: Rect::default(),
+    })
+    .unwrap();
+    drop(tx);
+
+    let forwarded: Vec<_> = std::iter::from_fn(|| events.blocking_recv())
+      .filter_map(icon_event)
+      .collect();
+    assert_eq!(forwarded.len(), 1);
+    assert!(matches!(forwarded[0], TrayEvent::IconEvent(TrayIconEvent::Enter { .. })));
+  }
+}
@@ -24,3 +24,60 @@ pub(crate) mod icon {
     (rgba, width, height)
   }
 }
+
+pub(crate) mod process {
+  use std::process::Command;
+
+  /// Starts `command` in a process group of its own, so [`kill_group`] also reaches whatever it spawns.
+  pub(crate) fn new_group(command: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    {
+      use std::os::unix::process::CommandExt;
+      command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+      use std::os::windows::process::CommandExt;
+      const CREATE_NEW_PROCESS_GROUP: u32 = 0x200;
+      command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+    command
+  }
+
+  /// Kills the process `pid` started through [`new_group`] together with its descendants.
+  pub(crate) fn kill_group(pid: u32) {
+    #[cfg(unix)]
+    unsafe {
+      libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+      let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID"])
+        .arg(pid.to_string())
+        .output();
+    }
+  }
+}
+
+/// Helpers shared by the unit tests.
+#[cfg(test)]
+pub(crate) mod test {
+  use std::path::PathBuf;
+
+  pub(crate) fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap()
+      .block_on(f)
+  }
+
+  /// A path in the temp dir unique to this run, with whatever an earlier run left there removed.
+  pub(crate) fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("timer-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&path);
+    path
+  }
+}